@@ -0,0 +1,119 @@
+// Hit-test cost on the large waves of later levels: the occupancy grid behind
+// `Invaders::hit_invader_at` against the linear scan it replaced. The grid side
+// also pays for retiming the army on every kill, as it does in the game, so on
+// small waves the scan can come out ahead; the bench reports where that stops.
+//
+//   cargo bench --bench collisions
+use invaders::{frame::sized_frame, invaders::Invaders, level::Level, rng::Rng};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+// Shots checked per frame, each missing or hitting somewhere in the wave
+const SHOTS: usize = 8;
+// Levels timed, boss levels left out as they have no wave
+const LEVELS: [u16; 9] = [5, 11, 21, 41, 61, 101, 201, 501, 999];
+// Timed runs per level, each on a fresh wave, after some untimed warm-up runs
+const RUNS: usize = 1001;
+const WARM_UP: usize = 50;
+
+fn wave(level: u16) -> (Invaders, Vec<(usize, usize)>) {
+    let frame = sized_frame(600, 300);
+    let mut rng = Rng::new(1);
+    let mut invaders = Invaders::default();
+    invaders.populate(&Level::starting_at(level), &frame, &mut rng);
+    let positions = invaders
+        .iter()
+        .map(|invader| (invader.x, invader.y))
+        .collect();
+    (invaders, positions)
+}
+
+// Clear the wave a few shots at a time, as a long game would, with one miss per volley
+fn grid(invaders: &mut Invaders, targets: &[(usize, usize)]) -> usize {
+    let mut kills = 0;
+    for volley in targets.chunks(SHOTS - 1) {
+        black_box(invaders.hit_invader_at(0, 0, u8::MAX));
+        for &(x, y) in volley {
+            // Enough damage to get through any armour in one shot
+            kills += invaders
+                .hit_invader_at(x, y, u8::MAX)
+                .is_some_and(|hit| hit.died) as usize;
+        }
+    }
+    kills
+}
+
+// The previous storage: a plain list searched front to back, shifting on removal
+fn linear(army: &mut Vec<(usize, usize)>, targets: &[(usize, usize)]) -> usize {
+    let mut kills = 0;
+    for volley in targets.chunks(SHOTS - 1) {
+        black_box(army.iter().position(|&cell| cell == (0, 0)));
+        for target in volley {
+            if let Some(index) = army.iter().position(|cell| cell == target) {
+                army.remove(index);
+                kills += 1;
+            }
+        }
+    }
+    kills
+}
+
+// Time `f` on a fresh copy of the wave: untimed warm-up runs first, then the median of the rest
+fn median<T>(runs: usize, mut setup: impl FnMut() -> T, mut f: impl FnMut(T) -> usize) -> Duration {
+    let mut times: Vec<Duration> = (0..WARM_UP + runs)
+        .map(|_| {
+            let input = setup();
+            let start = Instant::now();
+            black_box(f(input));
+            start.elapsed()
+        })
+        .skip(WARM_UP)
+        .collect();
+    times.sort_unstable();
+    times[times.len() / 2]
+}
+
+fn main() {
+    let mut crossover = None;
+    for level in LEVELS {
+        let (mut invaders, positions) = wave(level);
+        let mut targets = positions.clone();
+        Rng::new(2).shuffle(&mut targets);
+        let kills = grid(&mut invaders, &targets);
+        assert_eq!(kills, linear(&mut positions.clone(), &targets));
+
+        let linear_time = median(
+            RUNS,
+            || positions.clone(),
+            |mut army| linear(&mut army, &targets),
+        );
+        let grid_time = median(
+            RUNS,
+            || wave(level).0,
+            |mut invaders| grid(&mut invaders, &targets),
+        );
+        let speed_up = linear_time.as_secs_f64() / grid_time.as_secs_f64().max(f64::EPSILON);
+        // The first level from which the grid stays ahead
+        if speed_up > 1.0 {
+            crossover = crossover.or(Some(level));
+        } else {
+            crossover = None;
+        }
+        println!(
+            "level {:>3}: {:>5} invaders  linear {:>10.3?}  grid {:>10.3?}  {:>6.1}x",
+            level, kills, linear_time, grid_time, speed_up
+        );
+    }
+    match crossover {
+        Some(level) => println!(
+            "median of {} runs: the grid is faster from level {} on",
+            RUNS, level
+        ),
+        None => println!(
+            "median of {} runs: the linear scan is faster up to level 999",
+            RUNS
+        ),
+    }
+}
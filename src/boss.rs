@@ -0,0 +1,362 @@
+// The mothership guarding every 10th level: a sprite several cells wide that
+// sweeps along the top of the playfield. It drops spreads of bombs, sends minions
+// down and sweeps a laser across the ships, attacking harder and more often as its
+// health runs down, and pays out big once destroyed.
+use crate::{
+    frame::{draw_text, Drawable, Frame},
+    invaders::{Invaders, Kind},
+    rng::Rng,
+    shot::{Owner, Projectiles, Shot, Velocity, BOMB_INTERVAL},
+};
+use rusty_time::Timer;
+use std::time::Duration;
+
+// Levels that are a multiple of this one are boss levels
+pub const BOSS_EVERY: u16 = 10;
+
+// Eyes are drawn by phase, see `Phase::eyes`
+const SPRITE: [&str; 3] = ["  _/^\\_  ", " /o o o\\ ", "<=======>"];
+const WIDTH: usize = 9;
+const HEIGHT: usize = 3;
+// Row 1 is taken by the hot-seat scores and the versus opponent line
+const BAR_ROW: usize = 2;
+
+// The laser warms up harmlessly before it burns
+const LASER_CHARGE: Duration = Duration::from_millis(700);
+const LASER_FIRE: Duration = Duration::from_millis(1500);
+// Minions alive at most, so they can't bury the playfield
+const MAX_MINIONS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Calm,    // Above two thirds of its health
+    Angry,   // Down to a third
+    Furious, // The rest
+}
+
+impl Phase {
+    fn of(health: u16, max_health: u16) -> Self {
+        if health * 3 > max_health * 2 {
+            Phase::Calm
+        } else if health * 3 > max_health {
+            Phase::Angry
+        } else {
+            Phase::Furious
+        }
+    }
+
+    fn move_interval(&self) -> Duration {
+        Duration::from_millis(match self {
+            Phase::Calm => 300,
+            Phase::Angry => 200,
+            Phase::Furious => 120,
+        })
+    }
+
+    fn attack_interval(&self) -> Duration {
+        Duration::from_millis(match self {
+            Phase::Calm => 1500,
+            Phase::Angry => 1100,
+            Phase::Furious => 800,
+        })
+    }
+
+    // The attacks it picks from, growing with each phase
+    fn attacks(&self) -> &'static [Attack] {
+        match self {
+            Phase::Calm => &[Attack::Spread],
+            Phase::Angry => &[Attack::Spread, Attack::Minions],
+            Phase::Furious => &[Attack::Spread, Attack::Minions, Attack::Laser],
+        }
+    }
+
+    fn eyes(&self) -> char {
+        match self {
+            Phase::Calm => 'o',
+            Phase::Angry => 'O',
+            Phase::Furious => '@',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Attack {
+    Spread,
+    Minions,
+    Laser,
+}
+
+pub struct Boss {
+    pub x: usize, // Left edge of the sprite
+    pub y: usize, // Top row of the sprite
+    level: u16,
+    health: u16,
+    max_health: u16,
+    direction: i32,
+    move_timer: Timer,
+    attack_timer: Timer,
+    laser: Option<Timer>, // Charging, then firing straight down from the middle of the sprite
+}
+
+impl Boss {
+    // The boss of `level`, centered under its health bar
+    pub fn new(level: u16, frame: &Frame) -> Self {
+        let max_health = health(level);
+        let phase = Phase::of(max_health, max_health);
+        Self {
+            x: (frame.len().saturating_sub(WIDTH) / 2).max(1),
+            y: BAR_ROW + 1,
+            level,
+            health: max_health,
+            max_health,
+            direction: 1,
+            move_timer: Timer::new(phase.move_interval()),
+            attack_timer: Timer::new(phase.attack_interval()),
+            laser: None,
+        }
+    }
+
+    pub fn health(&self) -> u16 {
+        self.health
+    }
+
+    pub fn phase(&self) -> Phase {
+        Phase::of(self.health, self.max_health)
+    }
+
+    pub fn defeated(&self) -> bool {
+        self.health == 0
+    }
+
+    // Score for destroying it: 100 on level 10, 50 more every boss after that
+    pub fn points(&self) -> u32 {
+        100 + 50 * (self.level / BOSS_EVERY).saturating_sub(1) as u32
+    }
+
+    // Column the middle of the sprite is over, where the laser fires from
+    pub fn center(&self) -> usize {
+        self.x + WIDTH / 2
+    }
+
+    // Whether a cell is part of the ship, gaps in the sprite excluded
+    pub fn covers(&self, x: usize, y: usize) -> bool {
+        let (Some(column), Some(row)) = (x.checked_sub(self.x), y.checked_sub(self.y)) else {
+            return false;
+        };
+        SPRITE
+            .get(row)
+            .and_then(|line| line.chars().nth(column))
+            .is_some_and(|glyph| glyph != ' ')
+    }
+
+    // Cells of the ship, for hit tests and observations
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..HEIGHT)
+            .flat_map(move |row| (0..WIDTH).map(move |column| (self.x + column, self.y + row)))
+            .filter(|&(x, y)| self.covers(x, y))
+    }
+
+    // First row below the sprite, where its bombs and laser start
+    pub fn bottom(&self) -> usize {
+        self.y + HEIGHT
+    }
+
+    // Column the laser is burning, if it's firing
+    pub fn laser(&self) -> Option<usize> {
+        self.laser
+            .as_ref()
+            .filter(|timer| timer.remaining() <= LASER_FIRE)
+            .map(|_| self.center())
+    }
+
+    // Stop the laser, charging or burning
+    pub fn cease_fire(&mut self) {
+        self.laser = None;
+    }
+
+    // Take `damage`, returning true if it tipped the boss into its next phase
+    pub fn hit(&mut self, damage: u8) -> bool {
+        let before = self.phase();
+        self.health = self.health.saturating_sub(damage as u16);
+        let phase = self.phase();
+        if phase != before {
+            // Angrier at once, rather than after the calmer timers run out
+            self.move_timer.set_duration(phase.move_interval());
+            self.attack_timer.set_duration(phase.attack_interval());
+            self.attack_timer.set_remaining(phase.attack_interval() / 2);
+        }
+        phase != before
+    }
+
+    // Move and attack; the rng picks attacks, bombs go to `projectiles` and minions join `invaders`
+    pub fn update(
+        &mut self,
+        delta: Duration,
+        frame: &Frame,
+        rng: &mut Rng,
+        projectiles: &mut Projectiles,
+        invaders: &mut Invaders,
+    ) {
+        if let Some(laser) = self.laser.as_mut() {
+            laser.tick(delta);
+            if laser.finished() {
+                self.laser = None;
+            }
+        }
+
+        self.move_timer.tick(delta);
+        if self.move_timer.finished() {
+            self.move_timer.reset();
+            let right = frame.len().saturating_sub(WIDTH + 1).max(1);
+            if (self.direction < 0 && self.x <= 1) || (self.direction > 0 && self.x >= right) {
+                self.direction = -self.direction;
+            }
+            self.x = self
+                .x
+                .saturating_add_signed(self.direction as isize)
+                .clamp(1, right);
+        }
+
+        // Hold fire while the laser sweeps
+        if self.laser.is_some() {
+            return;
+        }
+        self.attack_timer.tick(delta);
+        if !self.attack_timer.finished() {
+            return;
+        }
+        self.attack_timer.reset();
+        let attacks = self.phase().attacks();
+        match attacks[rng.below(attacks.len())] {
+            Attack::Spread => self.spread(projectiles),
+            Attack::Minions => self.minions(frame, invaders),
+            Attack::Laser => self.laser = Some(Timer::new(LASER_CHARGE + LASER_FIRE)),
+        }
+    }
+
+    // Bombs fanning out from the middle, with two more from the wings once angry
+    fn spread(&self, projectiles: &mut Projectiles) {
+        let bottom = self.bottom();
+        for dx in -1..=1 {
+            let velocity = Velocity {
+                dx,
+                ..Velocity::down(BOMB_INTERVAL)
+            };
+            projectiles.fire(Shot::new(self.center(), bottom, Owner::Ufo, velocity));
+        }
+        if self.phase() != Phase::Calm {
+            for x in [self.x, self.x + WIDTH - 1] {
+                let velocity = Velocity::down(BOMB_INTERVAL);
+                projectiles.fire(Shot::new(x, bottom, Owner::Ufo, velocity));
+            }
+        }
+    }
+
+    // A row of soldiers just under the ship
+    fn minions(&self, frame: &Frame, invaders: &mut Invaders) {
+        let count = match self.phase() {
+            Phase::Furious => 5,
+            _ => 3,
+        };
+        let y = self.bottom() + 1;
+        let room = MAX_MINIONS.saturating_sub(invaders.remaining());
+        (0..WIDTH)
+            .step_by(2)
+            .map(|column| self.x + column)
+            .filter(|&x| x + 1 < frame.len())
+            .take(count.min(room))
+            .for_each(|x| {
+                invaders.spawn(Kind::Soldier, x, y);
+            });
+    }
+}
+
+impl Drawable for Boss {
+    fn draw(&self, frame: &mut Frame) {
+        let eyes = self.phase().eyes();
+        for (row, line) in SPRITE.iter().enumerate() {
+            let line: String = line
+                .chars()
+                .map(|glyph| if glyph == 'o' { eyes } else { glyph })
+                .collect();
+            for (column, glyph) in line.chars().enumerate() {
+                if glyph != ' ' {
+                    draw_text(frame, self.x + column, self.y + row, &glyph.to_string());
+                }
+            }
+        }
+
+        // A flickering guide while charging, a solid beam once it burns
+        if self.laser.is_some() {
+            let firing = self.laser().is_some();
+            let height = frame.first().map_or(0, Vec::len);
+            for y in self.bottom()..height.saturating_sub(1) {
+                if firing {
+                    draw_text(frame, self.center(), y, "|");
+                } else if y % 2 == 0 {
+                    draw_text(frame, self.center(), y, ":");
+                }
+            }
+        }
+
+        // Health bar on its own row, with the sprite below it
+        let width = frame.len().saturating_sub(8).min(30);
+        let filled = (width * self.health as usize).div_ceil(self.max_health as usize);
+        let bar = format!(
+            "BOSS [{}{}]",
+            "=".repeat(filled),
+            " ".repeat(width - filled)
+        );
+        let x = frame.len().saturating_sub(bar.len()) / 2;
+        draw_text(frame, x, BAR_ROW, &bar);
+    }
+}
+
+// Hit points of the boss of `level`, growing by 6 a boss up to 150
+fn health(level: u16) -> u16 {
+    (24 + 6 * (level / BOSS_EVERY).saturating_sub(1)).min(150)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::sized_frame;
+
+    #[test]
+    fn should_get_angrier_as_health_runs_down() {
+        // given
+        let mut boss = Boss::new(10, &sized_frame(40, 20));
+        // when
+        let phases: Vec<(bool, Phase)> = (0..24).map(|_| (boss.hit(1), boss.phase())).collect();
+        // then
+        assert_eq!((false, Phase::Calm), phases[6]);
+        assert_eq!((true, Phase::Angry), phases[7]);
+        assert_eq!((true, Phase::Furious), phases[15]);
+        assert!(boss.defeated());
+    }
+
+    #[test]
+    fn should_only_be_hit_on_its_sprite() {
+        // given
+        let boss = Boss::new(20, &sized_frame(40, 20));
+        // then
+        assert!(boss.covers(boss.x, boss.y + 2));
+        assert!(!boss.covers(boss.x, boss.y));
+        assert!(!boss.covers(boss.x + WIDTH, boss.y + 2));
+        assert_eq!(30, boss.health());
+        assert_eq!(150, boss.points());
+    }
+
+    #[test]
+    fn should_keep_row_one_free_for_the_mode_huds() {
+        // given
+        let mut frame = sized_frame(40, 20);
+        let boss = Boss::new(10, &frame);
+        // when
+        boss.draw(&mut frame);
+        // then
+        let row = |y: usize| -> String { frame.iter().map(|column| column[y]).collect() };
+        assert_eq!(" ".repeat(40), row(1));
+        assert!(row(BAR_ROW).contains("BOSS ["));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    difficulty::DifficultyLevel,
+    overlay::Target,
+    sim::{Bot, SimConfig},
+};
+use std::{error::Error, fmt, time::Duration};
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Play,
+    Simulate(SimConfig), // `invaders simulate ...`: headless bot games for balancing
+    Host(u16),           // `--host PORT`: wait for a versus opponent
+    Join(String),        // `--join ADDR`: play versus against a host
+    Serve(u16),          // `invaders serve --port PORT`: host games over telnet
+    Watch(String),       // `invaders watch ADDR`: spectate a broadcast game
+    Edit(String),        // `invaders edit FILE`: edit a level of a level pack
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub seed: Option<u64>, // Fixed seed for every game, random per game when unset
+    pub demo_after: Duration, // Menu idle time before the attract-mode demo starts
+    pub broadcast: Option<String>, // Socket path or TCP address to stream frames to spectators
+    pub export: Option<Target>, // Where to publish live game state as JSON
+    pub level: u16,        // Level new games start at, for practice
+    pub pack: Option<String>, // Directory of hand-made levels to play
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            command: Command::Play,
+            seed: None,
+            demo_after: Duration::from_secs(20),
+            broadcast: None,
+            export: None,
+            level: 1,
+            pack: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CliError(pub String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for CliError {}
+
+impl Options {
+    // Parse command-line arguments, excluding the program name
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        let mut args = args.into_iter().peekable();
+        if args.peek().map(String::as_str) == Some("simulate") {
+            args.next();
+            options.command = Command::Simulate(SimConfig::default());
+        } else if args.peek().map(String::as_str) == Some("serve") {
+            args.next();
+            options.command = Command::Serve(2323);
+        } else if args.peek().map(String::as_str) == Some("watch") {
+            let arg = args.next().unwrap_or_default();
+            options.command = Command::Watch(parse_value(&arg, args.next())?);
+        } else if args.peek().map(String::as_str) == Some("edit") {
+            let arg = args.next().unwrap_or_default();
+            options.command = Command::Edit(parse_value(&arg, args.next())?);
+        }
+
+        while let Some(arg) = args.next() {
+            if let Command::Simulate(config) = &mut options.command {
+                if parse_simulate_arg(config, &arg, &mut args)? {
+                    continue;
+                }
+            }
+            if let Command::Serve(port) = &mut options.command {
+                if arg == "--port" {
+                    *port = parse_value(&arg, args.next())?;
+                    continue;
+                }
+            }
+            match arg.as_str() {
+                "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+                "--demo-after" => {
+                    options.demo_after = Duration::from_secs(parse_value(&arg, args.next())?)
+                }
+                "--export-file" => {
+                    options.export = Some(Target::File(parse_value(&arg, args.next())?))
+                }
+                "--export-socket" => {
+                    options.export = Some(Target::Socket(parse_value(&arg, args.next())?))
+                }
+                "--level" => options.level = parse_value(&arg, args.next())?,
+                "--pack" => options.pack = Some(parse_value(&arg, args.next())?),
+                "--broadcast" => options.broadcast = Some(parse_value(&arg, args.next())?),
+                "--host" | "--join" if options.command != Command::Play => {
+                    return Err(CliError(format!(
+                        "`{}` can't be combined with other modes",
+                        arg
+                    )))
+                }
+                "--host" => options.command = Command::Host(parse_value(&arg, args.next())?),
+                "--join" => options.command = Command::Join(parse_value(&arg, args.next())?),
+                _ => return Err(CliError(format!("unknown argument `{}`", arg))),
+            }
+        }
+        if let Command::Simulate(config) = &mut options.command {
+            config.seed = options.seed.unwrap_or(config.seed);
+        }
+        Ok(options)
+    }
+}
+
+// Handle a `simulate` flag, returning false if it isn't one
+fn parse_simulate_arg<I: Iterator<Item = String>>(
+    config: &mut SimConfig,
+    arg: &str,
+    args: &mut I,
+) -> Result<bool, CliError> {
+    match arg {
+        "--games" => config.games = parse_value(arg, args.next())?,
+        "--threads" => config.threads = parse_value(arg, args.next())?,
+        "--time-limit" => config.time_limit = Duration::from_secs(parse_value(arg, args.next())?),
+        "--csv" => config.csv = true,
+        "--difficulty" => {
+            let name: String = parse_value(arg, args.next())?;
+            config.difficulty = DifficultyLevel::parse(&name)
+                .ok_or_else(|| CliError(format!("unknown difficulty `{}`", name)))?;
+        }
+        "--bot" => {
+            let name: String = parse_value(arg, args.next())?;
+            config.bot =
+                Bot::parse(&name).ok_or_else(|| CliError(format!("unknown bot `{}`", name)))?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, CliError> {
+    let value = value.ok_or_else(|| CliError(format!("`{}` needs a value", flag)))?;
+    value
+        .parse()
+        .map_err(|_| CliError(format!("invalid value `{}` for `{}`", value, flag)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn should_parse_seed() {
+        // when
+        let actual = Options::parse(args("--seed 1234")).unwrap();
+        // then
+        assert_eq!(Some(1234), actual.seed);
+    }
+
+    #[test]
+    fn should_parse_demo_delay() {
+        // when
+        let actual = Options::parse(args("--demo-after 5")).unwrap();
+        // then
+        assert_eq!(Duration::from_secs(5), actual.demo_after);
+    }
+
+    #[test]
+    fn should_parse_simulate_command() {
+        // when
+        let actual = Options::parse(args(
+            "simulate --games 1000 --difficulty hard --bot greedy --seed 9",
+        ))
+        .unwrap();
+        // then
+        assert_eq!(
+            Command::Simulate(SimConfig {
+                games: 1000,
+                difficulty: DifficultyLevel::Hard,
+                bot: Bot::Greedy,
+                seed: 9,
+                ..SimConfig::default()
+            }),
+            actual.command
+        );
+    }
+
+    #[test]
+    fn should_parse_edit_command() {
+        // when
+        let actual = Options::parse(args("edit pack/level03.txt")).unwrap();
+        // then
+        assert_eq!(
+            Command::Edit(String::from("pack/level03.txt")),
+            actual.command
+        );
+        assert!(Options::parse(args("edit")).is_err());
+    }
+
+    #[test]
+    fn should_parse_practice_level() {
+        assert_eq!(1, Options::parse(args("")).unwrap().level);
+        assert_eq!(40, Options::parse(args("--level 40")).unwrap().level);
+        assert!(Options::parse(args("--level forty")).is_err());
+        assert_eq!(
+            Some(String::from("levels")),
+            Options::parse(args("--pack levels --level 3"))
+                .unwrap()
+                .pack
+        );
+    }
+
+    #[test]
+    fn should_parse_versus_commands() {
+        assert_eq!(
+            Command::Host(4000),
+            Options::parse(args("--host 4000")).unwrap().command
+        );
+        assert_eq!(
+            Command::Join(String::from("10.0.0.2:4000")),
+            Options::parse(args("--join 10.0.0.2:4000"))
+                .unwrap()
+                .command
+        );
+        assert!(Options::parse(args("--host 4000 --join 10.0.0.2:4000")).is_err());
+    }
+
+    #[test]
+    fn should_parse_serve_command() {
+        assert_eq!(
+            Command::Serve(2323),
+            Options::parse(args("serve")).unwrap().command
+        );
+        assert_eq!(
+            Command::Serve(4000),
+            Options::parse(args("serve --port 4000")).unwrap().command
+        );
+        assert!(Options::parse(args("--port 4000")).is_err());
+    }
+
+    #[test]
+    fn should_parse_spectator_options() {
+        assert_eq!(
+            Some(String::from("/tmp/inv.sock")),
+            Options::parse(args("--broadcast /tmp/inv.sock"))
+                .unwrap()
+                .broadcast
+        );
+        assert_eq!(
+            Command::Watch(String::from("/tmp/inv.sock")),
+            Options::parse(args("watch /tmp/inv.sock")).unwrap().command
+        );
+        assert!(Options::parse(args("watch")).is_err());
+    }
+
+    #[test]
+    fn should_reject_simulate_flags_when_playing() {
+        assert!(Options::parse(args("--games 10")).is_err());
+        assert!(Options::parse(args("simulate --bot smart")).is_err());
+    }
+
+    #[test]
+    fn should_reject_unknown_and_malformed_arguments() {
+        assert!(Options::parse(args("--turbo")).is_err());
+        assert!(Options::parse(args("--seed")).is_err());
+        assert!(Options::parse(args("--seed abc")).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{game::Game, invaders::Invader, rng::Rng, shot::SHOT_INTERVAL};
+use crossterm::event::KeyCode;
+use rusty_time::Timer;
+use std::{mem, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Fire,
+}
+
+// Something that drives the player ship: a human at the keyboard, a bot, a replay...
+pub trait Controller {
+    // Actions to apply to the game this tick
+    fn actions(&mut self, game: &Game, delta: Duration) -> Vec<Action>;
+}
+
+// Key bindings, so two players can share one keyboard
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Layout {
+    #[default]
+    Arrows, // Left/Right to move, Space or Enter to fire
+    Letters, // A/D to move, W to fire
+}
+
+// Human player: key presses are queued as they arrive and handed out on the next tick
+#[derive(Default)]
+pub struct Keyboard {
+    layout: Layout,
+    pending: Vec<Action>,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_layout(layout: Layout) -> Self {
+        Self {
+            layout,
+            pending: Vec::new(),
+        }
+    }
+
+    // Queue the action bound to a key, returning false for keys this layout doesn't use
+    pub fn press(&mut self, code: KeyCode) -> bool {
+        let action = match (self.layout, code) {
+            (Layout::Arrows, KeyCode::Left) => Action::MoveLeft,
+            (Layout::Arrows, KeyCode::Right) => Action::MoveRight,
+            (Layout::Arrows, KeyCode::Char(' ') | KeyCode::Enter) => Action::Fire,
+            (Layout::Letters, KeyCode::Char('a' | 'A')) => Action::MoveLeft,
+            (Layout::Letters, KeyCode::Char('d' | 'D')) => Action::MoveRight,
+            (Layout::Letters, KeyCode::Char('w' | 'W')) => Action::Fire,
+            _ => return false,
+        };
+        self.pending.push(action);
+        true
+    }
+}
+
+impl Controller for Keyboard {
+    fn actions(&mut self, _game: &Game, _delta: Duration) -> Vec<Action> {
+        mem::take(&mut self.pending)
+    }
+}
+
+// Heuristic bot flying the first ship: chases the nearest invader column and fires once lined up
+pub struct Autopilot {
+    reaction_timer: Timer, // Limits the bot to human-like key rates
+}
+
+impl Autopilot {
+    pub fn new(reaction: Duration) -> Self {
+        Self {
+            reaction_timer: Timer::new(reaction),
+        }
+    }
+
+    // Column to fire from to hit the closest visible invader, preferring the lowest on ties,
+    // or the boss once its minions are gone, off its middle where the bombs fall straight down
+    fn target(game: &Game) -> Option<usize> {
+        let x = game.players[0].x();
+        game.invaders
+            .iter()
+            .filter(|invader| invader.is_visible)
+            .map(|invader| (Self::lead(game, invader), invader.y))
+            .min_by_key(|&(column, y)| (column.abs_diff(x), usize::MAX - y))
+            .map(|(column, _)| column)
+            .or_else(|| game.boss.as_ref().map(|boss| boss.x + 2))
+    }
+
+    // Where an invader will be once a shot fired now has climbed to its row
+    fn lead(game: &Game, invader: &Invader) -> usize {
+        let (x, y) = (invader.x, invader.y);
+        let travel = SHOT_INTERVAL * game.players[0].y().saturating_sub(y + 1) as u32;
+        let until_step = game.invaders.time_to_step();
+        let steps = if travel < until_step {
+            0
+        } else {
+            1 + ((travel - until_step).as_secs_f32() / game.invaders.step_interval().as_secs_f32())
+                as i32
+        };
+        (x as i32 + steps * game.invaders.heading(invader)).max(0) as usize
+    }
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(60))
+    }
+}
+
+impl Controller for Autopilot {
+    fn actions(&mut self, game: &Game, delta: Duration) -> Vec<Action> {
+        self.reaction_timer.tick(delta);
+        if !self.reaction_timer.finished() {
+            return Vec::new();
+        }
+        self.reaction_timer.reset();
+
+        let x = game.players[0].x();
+        match Self::target(game) {
+            Some(target) if target < x => vec![Action::MoveLeft],
+            Some(target) if target > x => vec![Action::MoveRight],
+            Some(_) => vec![Action::Fire],
+            None => Vec::new(),
+        }
+    }
+}
+
+// Baseline bot that presses a random key every so often
+pub struct RandomBot {
+    rng: Rng,
+    reaction_timer: Timer,
+}
+
+impl RandomBot {
+    pub fn new(rng: Rng) -> Self {
+        Self {
+            rng,
+            reaction_timer: Timer::new(Duration::from_millis(60)),
+        }
+    }
+}
+
+impl Controller for RandomBot {
+    fn actions(&mut self, _game: &Game, delta: Duration) -> Vec<Action> {
+        self.reaction_timer.tick(delta);
+        if !self.reaction_timer.finished() {
+            return Vec::new();
+        }
+        self.reaction_timer.reset();
+        let actions = [Action::MoveLeft, Action::MoveRight, Action::Fire];
+        vec![actions[self.rng.below(actions.len())]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{difficulty::Difficulty, frame::sized_frame, rng::Rng};
+
+    #[test]
+    fn should_map_keys_to_queued_actions() {
+        // given
+        let frame = sized_frame(40, 20);
+        let game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        let mut keyboard = Keyboard::new();
+        // when
+        keyboard.press(KeyCode::Left);
+        keyboard.press(KeyCode::Char(' '));
+        let ignored = keyboard.press(KeyCode::Char('x'));
+        // then
+        assert!(!ignored);
+        assert_eq!(
+            vec![Action::MoveLeft, Action::Fire],
+            keyboard.actions(&game, Duration::ZERO)
+        );
+        assert!(keyboard.actions(&game, Duration::ZERO).is_empty());
+    }
+
+    #[test]
+    fn should_only_accept_keys_from_its_own_layout() {
+        // given
+        let mut arrows = Keyboard::new();
+        let mut letters = Keyboard::with_layout(Layout::Letters);
+        // when
+        let keys = [KeyCode::Char('a'), KeyCode::Right, KeyCode::Char('w')];
+        let accepted: Vec<(bool, bool)> = keys
+            .iter()
+            .map(|&code| (arrows.press(code), letters.press(code)))
+            .collect();
+        // then
+        assert_eq!(vec![(false, true), (true, false), (false, true)], accepted);
+    }
+
+    #[test]
+    fn should_clear_first_wave_with_autopilot() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        let mut autopilot = Autopilot::default();
+        let delta = Duration::from_millis(10);
+        // when
+        let cleared = (0..6000).any(|_| {
+            let actions = autopilot.actions(&game, delta);
+            game.step(delta, &[actions], &frame).level_cleared
+        });
+        // then
+        assert!(cleared);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{
+    difficulty::{Difficulty, DifficultyLevel},
+    rng::Rng,
+};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    // Today's date in UTC, so everyone gets the same challenge regardless of time zone
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::from_days((secs / 86_400) as i64)
+    }
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    pub fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Self { year, month, day }
+    }
+
+    // Seed shared by everyone playing on this date, readable as YYYYMMDD
+    pub fn seed(&self) -> u64 {
+        (self.year as u64) * 10_000 + (self.month as u64) * 100 + self.day as u64
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+// Twists applied on top of the day's difficulty preset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Modifier {
+    SwiftArmy,  // Army marches faster
+    SingleShot, // Only one shot on screen at a time
+    RapidFire,  // Shorter cooldown between shots
+    Relentless, // Army speeds up sharply as it thins out
+}
+
+impl Modifier {
+    const ALL: [Modifier; 4] = [
+        Modifier::SwiftArmy,
+        Modifier::SingleShot,
+        Modifier::RapidFire,
+        Modifier::Relentless,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Modifier::SwiftArmy => "swift army",
+            Modifier::SingleShot => "single shot",
+            Modifier::RapidFire => "rapid fire",
+            Modifier::Relentless => "relentless",
+        }
+    }
+
+    fn apply(&self, difficulty: &mut Difficulty) {
+        match self {
+            Modifier::SwiftArmy => difficulty.invader_speed = difficulty.invader_speed * 3 / 4,
+            Modifier::SingleShot => difficulty.max_shots = Some(1),
+            Modifier::RapidFire => difficulty.player_fire_rate /= 2,
+            Modifier::Relentless => {
+                difficulty.speed_curve.fastest /= 2.0;
+                difficulty.speed_curve.exponent += 1.0;
+            }
+        }
+    }
+}
+
+pub struct Challenge {
+    pub date: Date,
+    pub seed: u64,
+    pub level: DifficultyLevel,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl Challenge {
+    // Derive the whole challenge from the date so every player gets the same one
+    pub fn for_date(date: Date) -> Self {
+        let seed = date.seed();
+        let mut rng = Rng::new(seed);
+        // Skip Easy: the daily run should be a challenge
+        let level = DifficultyLevel::ALL[1 + rng.below(DifficultyLevel::ALL.len() - 1)];
+        let mut modifiers = Modifier::ALL.to_vec();
+        rng.shuffle(&mut modifiers);
+        modifiers.truncate(rng.below(3));
+        Self {
+            date,
+            seed,
+            level,
+            modifiers,
+        }
+    }
+
+    pub fn today() -> Self {
+        Self::for_date(Date::today())
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        let mut difficulty = Difficulty::new(self.level);
+        for modifier in &self.modifiers {
+            modifier.apply(&mut difficulty);
+        }
+        difficulty
+    }
+
+    // One-line summary for the menu, e.g. "2024-05-01 Hard, swift army"
+    pub fn describe(&self) -> String {
+        let mut parts = vec![self.level.name()];
+        parts.extend(self.modifiers.iter().map(Modifier::name));
+        format!("{} {}", self.date, parts.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+    pub level: u16,
+}
+
+// Local scores for a single day's challenge, one line per player
+pub struct Leaderboard {
+    path: PathBuf,
+    pub entries: Vec<Entry>,
+}
+
+impl Leaderboard {
+    // Load the board for `date` from `dir`, starting empty if there is none yet
+    pub fn load(dir: &Path, date: &Date) -> io::Result<Self> {
+        let path = dir.join(format!("daily-{}.txt", date));
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().filter_map(parse_entry).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, entries })
+    }
+
+    pub fn attempted(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.name == name)
+    }
+
+    // 1-based position of a player on the board
+    pub fn rank(&self, name: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.name == name)
+            .map(|index| index + 1)
+    }
+
+    // Store a player's result, replacing their previous entry, and save the board
+    pub fn record(&mut self, entry: Entry) -> io::Result<()> {
+        self.entries.retain(|existing| existing.name != entry.name);
+        self.entries.push(entry);
+        self.entries
+            .sort_by(|a, b| b.score.cmp(&a.score).then(b.level.cmp(&a.level)));
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents: String = self
+            .entries
+            .iter()
+            .map(|entry| format!("{} {} {}\n", entry.name, entry.score, entry.level))
+            .collect();
+        fs::write(&self.path, contents)
+    }
+}
+
+// Close out the scored daily attempt in progress, if any, on a win or a loss alike.
+// Returns the player's rank and the size of the board.
+pub fn finish(run: &mut Option<Leaderboard>, entry: Entry) -> io::Result<Option<(usize, usize)>> {
+    let Some(mut board) = run.take() else {
+        return Ok(None);
+    };
+    let name = entry.name.clone();
+    board.record(entry)?;
+    Ok(board.rank(&name).map(|rank| (rank, board.entries.len())))
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let mut fields = line.split_whitespace();
+    Some(Entry {
+        name: fields.next()?.to_string(),
+        score: fields.next()?.parse().ok()?,
+        level: fields.next()?.parse().ok()?,
+    })
+}
+
+// Where local game data such as leaderboards is kept
+pub fn data_dir() -> PathBuf {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map_or_else(|| PathBuf::from("."), PathBuf::from)
+        .join(".invaders")
+}
+
+// Name the local player's results are recorded under
+pub fn player_name() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .ok()
+        .and_then(|name| name.split_whitespace().next().map(String::from))
+        .unwrap_or_else(|| String::from("player"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_convert_days_to_civil_date() {
+        assert_eq!("1970-01-01", Date::from_days(0).to_string());
+        assert_eq!("2000-02-29", Date::from_days(11_016).to_string());
+        assert_eq!("2024-12-31", Date::from_days(20_088).to_string());
+    }
+
+    #[test]
+    fn should_derive_same_challenge_for_same_date() {
+        // given
+        let date = Date::from_days(20_000);
+        // when
+        let first = Challenge::for_date(date);
+        let second = Challenge::for_date(date);
+        // then
+        assert_eq!(20_241_004, first.seed);
+        assert_eq!(first.describe(), second.describe());
+        assert_ne!(DifficultyLevel::Easy, first.level);
+    }
+
+    #[test]
+    fn should_keep_one_entry_per_player_sorted_by_score() {
+        // given
+        let dir = env::temp_dir().join(format!("invaders-daily-test-{}", std::process::id()));
+        let date = Date::from_days(1);
+        let mut board = Leaderboard::load(&dir, &date).unwrap();
+        let entry = |name: &str, score| Entry {
+            name: name.to_string(),
+            score,
+            level: 1,
+        };
+        // when
+        board.record(entry("ann", 0)).unwrap();
+        board.record(entry("bob", 5)).unwrap();
+        board.record(entry("ann", 9)).unwrap();
+        let reloaded = Leaderboard::load(&dir, &date).unwrap();
+        // then
+        assert_eq!(vec![entry("ann", 9), entry("bob", 5)], reloaded.entries);
+        assert_eq!(Some(2), reloaded.rank("bob"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_record_a_finished_daily_run_once() {
+        // given
+        let dir = env::temp_dir().join(format!("invaders-daily-finish-{}", std::process::id()));
+        let date = Date::from_days(2);
+        let mut run = Some(Leaderboard::load(&dir, &date).unwrap());
+        let entry = Entry {
+            name: String::from("ann"),
+            score: 70_000,
+            level: 12,
+        };
+        // when
+        let first = finish(&mut run, entry.clone()).unwrap();
+        let second = finish(&mut run, entry.clone()).unwrap();
+        // then
+        assert_eq!(Some((1, 1)), first);
+        assert_eq!(None, second);
+        assert_eq!(vec![entry], Leaderboard::load(&dir, &date).unwrap().entries);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::Duration;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DifficultyLevel {
     Easy,
     Normal,
@@ -7,11 +8,56 @@ pub enum DifficultyLevel {
     Hardcore,
 }
 
-#[derive(Debug)]
+impl DifficultyLevel {
+    pub const ALL: [DifficultyLevel; 4] = [
+        DifficultyLevel::Easy,
+        DifficultyLevel::Normal,
+        DifficultyLevel::Hard,
+        DifficultyLevel::Hardcore,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyLevel::Easy => "Easy",
+            DifficultyLevel::Normal => "Normal",
+            DifficultyLevel::Hard => "Hard",
+            DifficultyLevel::Hardcore => "Hardcore",
+        }
+    }
+
+    // Look up a level by name, ignoring case
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+}
+
+// How the army's step interval shrinks as invaders are destroyed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedCurve {
+    pub fastest: f32,  // Fraction of the interval left when a single invader remains
+    pub exponent: f32, // 1.0 is linear, higher values speed up sooner
+}
+
+impl SpeedCurve {
+    // Multiplier for the step interval with `remaining` of `total` invaders alive
+    pub fn factor(&self, remaining: usize, total: usize) -> f32 {
+        if total <= 1 || remaining >= total {
+            return 1.0;
+        }
+        let ratio = remaining.saturating_sub(1) as f32 / (total - 1) as f32;
+        self.fastest + (1.0 - self.fastest) * ratio.powf(self.exponent)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Difficulty {
     pub invader_speed: Duration,
     pub player_fire_rate: Duration,
     pub max_shots: Option<usize>,
+    pub speed_curve: SpeedCurve,
+    pub invader_fire_rate: Option<Duration>, // Time between invader bombs, if they fire at all
 }
 
 impl Difficulty {
@@ -21,21 +67,41 @@ impl Difficulty {
                 invader_speed: Duration::from_millis(800), // Slower invaders
                 player_fire_rate: Duration::from_millis(400), // Faster fire rate
                 max_shots: Some(6),                        // More shots allowed
+                speed_curve: SpeedCurve {
+                    fastest: 0.3,
+                    exponent: 1.0,
+                },
+                invader_fire_rate: None,
             },
             DifficultyLevel::Normal => Self {
                 invader_speed: Duration::from_millis(600), // Moderate invader speed
                 player_fire_rate: Duration::from_millis(500), // Balanced fire rate
                 max_shots: Some(4),                        // Default shot limit
+                speed_curve: SpeedCurve {
+                    fastest: 0.15,
+                    exponent: 1.5,
+                },
+                invader_fire_rate: None,
             },
             DifficultyLevel::Hard => Self {
                 invader_speed: Duration::from_millis(400), // Faster invaders
                 player_fire_rate: Duration::from_millis(600), // Slower fire rate
                 max_shots: Some(2),                        // Default shot limit
+                speed_curve: SpeedCurve {
+                    fastest: 0.1,
+                    exponent: 2.0,
+                },
+                invader_fire_rate: Some(Duration::from_millis(2000)),
             },
             DifficultyLevel::Hardcore => Self {
                 invader_speed: Duration::from_millis(100), // Very fast invaders
                 player_fire_rate: Duration::from_millis(1000), // Slow fire rate
                 max_shots: Some(1),                        // Only one shot allowed at a time
+                speed_curve: SpeedCurve {
+                    fastest: 0.2,
+                    exponent: 1.0,
+                },
+                invader_fire_rate: Some(Duration::from_millis(1000)),
             },
         }
     }
@@ -46,3 +112,58 @@ impl Default for Difficulty {
         Self::new(DifficultyLevel::Normal)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factors(level: DifficultyLevel) -> Vec<f32> {
+        let curve = Difficulty::new(level).speed_curve;
+        [40, 30, 20, 10, 1]
+            .iter()
+            .map(|&remaining| (curve.factor(remaining, 40) * 100.0).round() / 100.0)
+            .collect()
+    }
+
+    #[test]
+    fn should_pin_easy_curve() {
+        assert_eq!(
+            vec![1.0, 0.82, 0.64, 0.46, 0.3],
+            factors(DifficultyLevel::Easy)
+        );
+    }
+
+    #[test]
+    fn should_pin_normal_curve() {
+        assert_eq!(
+            vec![1.0, 0.7, 0.44, 0.24, 0.15],
+            factors(DifficultyLevel::Normal)
+        );
+    }
+
+    #[test]
+    fn should_pin_hard_curve() {
+        assert_eq!(
+            vec![1.0, 0.6, 0.31, 0.15, 0.1],
+            factors(DifficultyLevel::Hard)
+        );
+    }
+
+    #[test]
+    fn should_pin_hardcore_curve() {
+        assert_eq!(
+            vec![1.0, 0.79, 0.59, 0.38, 0.2],
+            factors(DifficultyLevel::Hardcore)
+        );
+    }
+
+    #[test]
+    fn should_not_speed_up_a_single_invader_wave() {
+        // given
+        let curve = Difficulty::default().speed_curve;
+        // when
+        let actual = curve.factor(1, 1);
+        // then
+        assert_eq!(1.0, actual);
+    }
+}
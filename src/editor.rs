@@ -0,0 +1,540 @@
+// Level editor: `invaders edit pack/level03.txt`. The playfield is laid out as the
+// game would show it, with a side panel for the level's settings. Invaders and
+// bunker blocks are kept by screen cell while editing and turned back into the
+// level file's maps when saving or playtesting.
+use crate::{
+    difficulty::{Difficulty, DifficultyLevel},
+    frame::{draw_text, sized_frame, Drawable, Frame},
+    invaders::Kind,
+    level::Level,
+    pack::{level_number, LevelFile, Pack, PackError, Spot},
+    pattern::Pattern,
+    wave::Wave,
+};
+use crossterm::event::KeyCode;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+// Columns taken by the side panel, right of the playfield
+pub const PANEL_WIDTH: usize = 24;
+
+// What the editor needs from the loop running it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Request {
+    Save,
+    Playtest,
+    Quit,
+}
+
+// Settings shown in the side panel, top to bottom
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Wave, // Which formation is being edited; stepping past the last adds one
+    March,
+    Fastest,
+    Exponent,
+    Bombs,
+    Moves,
+}
+
+const FIELDS: [Field; 7] = [
+    Field::Name,
+    Field::Wave,
+    Field::March,
+    Field::Fastest,
+    Field::Exponent,
+    Field::Bombs,
+    Field::Moves,
+];
+
+pub struct Editor {
+    path: PathBuf,
+    level: u16,
+    settings: LevelFile, // Everything but the maps, which are rebuilt from the cells below
+    formations: Vec<BTreeMap<(usize, usize), Kind>>,
+    bunkers: BTreeSet<(usize, usize)>,
+    width: usize, // Playfield size, as the game's frame
+    height: usize,
+    pub cursor: (usize, usize),
+    formation: usize,
+    field: Option<usize>, // Selected panel field while the panel has the keys
+    pub status: String,
+    dirty: bool,
+    quitting: bool, // Quit was pressed once with unsaved changes
+}
+
+impl Editor {
+    // Open a level file, or start a blank level if it doesn't exist yet
+    pub fn open(path: &Path, frame: &Frame) -> Result<Self, PackError> {
+        let file = match fs::read_to_string(path) {
+            Ok(text) => LevelFile::parse(&text)
+                .map_err(|error| PackError(format!("{}:{}", path.display(), error)))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => LevelFile::default(),
+            Err(error) => return Err(PackError(format!("{}: {}", path.display(), error))),
+        };
+        let level = level_number(path).unwrap_or(1);
+        Ok(Self::new(path.to_path_buf(), level, file, frame))
+    }
+
+    pub fn new(path: PathBuf, level: u16, file: LevelFile, frame: &Frame) -> Self {
+        let mut editor = Self {
+            path,
+            level,
+            settings: LevelFile::default(),
+            formations: Vec::new(),
+            bunkers: BTreeSet::new(),
+            width: 0,
+            height: 0,
+            cursor: (0, 0),
+            formation: 0,
+            field: None,
+            status: String::new(),
+            dirty: false,
+            quitting: false,
+        };
+        editor.lay_out(file, frame);
+        editor.cursor = (editor.width / 2, 2);
+        editor
+    }
+
+    // Place a level on a playfield the size of `frame`, where the game would put it
+    fn lay_out(&mut self, file: LevelFile, frame: &Frame) {
+        self.width = frame.len();
+        self.height = frame.first().map_or(0, Vec::len);
+
+        let (width, _) = file.formation_size();
+        let left = (self.width.saturating_sub(width) / 2).max(1);
+        self.formations = file
+            .formations
+            .iter()
+            .map(|spots| {
+                spots
+                    .iter()
+                    .map(|spot| ((left + spot.x, 2 + spot.y), spot.kind))
+                    .collect()
+            })
+            .collect();
+        if self.formations.is_empty() {
+            self.formations.push(BTreeMap::new());
+        }
+
+        let (width, height) = file.bunkers_size();
+        let left = (self.width.saturating_sub(width) / 2).max(1);
+        let bottom = self.bunker_row();
+        self.bunkers = file
+            .bunkers
+            .iter()
+            .filter_map(|&(x, y)| Some((left + x, (bottom + y + 1).checked_sub(height)?)))
+            .collect();
+
+        self.formation = self.formation.min(self.formations.len() - 1);
+        self.settings = LevelFile {
+            formations: Vec::new(),
+            bunkers: Vec::new(),
+            ..file
+        };
+    }
+
+    // Fit a resized terminal, laying the level out again
+    pub fn resize(&mut self, frame: &Frame) {
+        let file = self.level_file();
+        self.lay_out(file, frame);
+        self.cursor = (
+            self.cursor.0.clamp(1, self.width.saturating_sub(2).max(1)),
+            self.cursor.1.clamp(2, self.bunker_row().max(2)),
+        );
+    }
+
+    // The lowest row bunkers sit on, just above the ships
+    fn bunker_row(&self) -> usize {
+        self.height.saturating_sub(5)
+    }
+
+    // The level as edited, with maps starting at their top-left corner
+    pub fn level_file(&self) -> LevelFile {
+        let left = self
+            .formations
+            .iter()
+            .flat_map(|cells| cells.keys())
+            .map(|&(x, _)| x)
+            .min()
+            .unwrap_or(0);
+        let formations = self
+            .formations
+            .iter()
+            .filter(|cells| !cells.is_empty())
+            .map(|cells| {
+                let mut spots: Vec<Spot> = cells
+                    .iter()
+                    .map(|(&(x, y), &kind)| Spot {
+                        x: x - left,
+                        y: y - 2,
+                        kind,
+                    })
+                    .collect();
+                // Row by row, the order they're read from a file
+                spots.sort_by_key(|spot| (spot.y, spot.x));
+                spots
+            })
+            .collect();
+        let left = self.bunkers.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let top = self.bunkers.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let mut bunkers: Vec<(usize, usize)> = self
+            .bunkers
+            .iter()
+            .map(|&(x, y)| (x - left, y - top))
+            .collect();
+        bunkers.sort_by_key(|&(x, y)| (y, x));
+        LevelFile {
+            formations,
+            bunkers,
+            ..self.settings.clone()
+        }
+    }
+
+    // A single-level pack to play the level in, starting from the formation on show
+    pub fn playtest(&mut self) -> Option<Level> {
+        let mut file = self.level_file();
+        let skipped = self.formations[..self.formation]
+            .iter()
+            .filter(|cells| !cells.is_empty())
+            .count();
+        file.formations.drain(..skipped.min(file.formations.len()));
+        if file.formations.is_empty() {
+            self.status = String::from("Place some invaders first");
+            return None;
+        }
+        let mut pack = Pack::default();
+        pack.insert(self.level, file);
+        Some(Level::starting_at(self.level).with_pack(Arc::new(pack)))
+    }
+
+    pub fn save(&mut self) {
+        let file = self.level_file();
+        if file.formations.is_empty() {
+            self.status = String::from("Place some invaders first");
+            return;
+        }
+        self.status = match fs::write(&self.path, file.to_text()) {
+            Ok(()) => {
+                self.dirty = false;
+                String::from("Saved")
+            }
+            Err(error) => format!("Not saved: {}", error),
+        };
+    }
+
+    // Handle a key press, returning what the loop running the editor has to do
+    pub fn press(&mut self, code: KeyCode) -> Option<Request> {
+        let quitting = std::mem::take(&mut self.quitting);
+        if let Some(index) = self.field {
+            match (FIELDS[index], code) {
+                (_, KeyCode::Tab | KeyCode::Esc) => self.field = None,
+                (_, KeyCode::Up) => self.field = Some(index.saturating_sub(1)),
+                (_, KeyCode::Down) => self.field = Some((index + 1).min(FIELDS.len() - 1)),
+                (field, KeyCode::Left) => self.adjust(field, false),
+                (field, KeyCode::Right) => self.adjust(field, true),
+                (Field::Name, KeyCode::Char(c)) => {
+                    self.settings.name.push(c);
+                    self.dirty = true;
+                }
+                (Field::Name, KeyCode::Backspace) => {
+                    self.settings.name.pop();
+                    self.dirty = true;
+                }
+                _ => return self.command(code, quitting),
+            }
+            return None;
+        }
+
+        let (x, y) = self.cursor;
+        match code {
+            KeyCode::Tab => self.field = Some(0),
+            KeyCode::Left => self.cursor.0 = (x - 1).max(1),
+            KeyCode::Right => self.cursor.0 = (x + 1).min(self.width.saturating_sub(2).max(1)),
+            KeyCode::Up => self.cursor.1 = (y - 1).max(2),
+            KeyCode::Down => self.cursor.1 = (y + 1).min(self.bunker_row().max(2)),
+            KeyCode::Char('x') => self.place(Kind::Soldier),
+            KeyCode::Char('X') => self.place(Kind::Elite),
+            KeyCode::Char('H') => self.place(Kind::Armoured),
+            KeyCode::Char('#') => {
+                self.bunkers.insert(self.cursor);
+                self.dirty = true;
+            }
+            KeyCode::Char(' ') | KeyCode::Backspace | KeyCode::Delete => {
+                self.formations[self.formation].remove(&self.cursor);
+                self.bunkers.remove(&self.cursor);
+                self.dirty = true;
+            }
+            _ => return self.command(code, quitting),
+        }
+        None
+    }
+
+    // Keys that work wherever the cursor is
+    fn command(&mut self, code: KeyCode, quitting: bool) -> Option<Request> {
+        match code {
+            KeyCode::Char('s') => Some(Request::Save),
+            KeyCode::Char('p') => Some(Request::Playtest),
+            KeyCode::Char('q') | KeyCode::Esc if self.dirty && !quitting => {
+                self.quitting = true;
+                self.status = String::from("Unsaved changes: q again to quit");
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Esc => Some(Request::Quit),
+            _ => None,
+        }
+    }
+
+    fn place(&mut self, kind: Kind) {
+        // The game drops invaders that start level with the bunkers
+        if self.cursor.1 >= self.bunker_row() {
+            self.status = String::from("Too low for invaders");
+            return;
+        }
+        self.formations[self.formation].insert(self.cursor, kind);
+        self.dirty = true;
+    }
+
+    fn adjust(&mut self, field: Field, up: bool) {
+        let default_curve = Difficulty::new(DifficultyLevel::Normal).speed_curve;
+        let settings = &mut self.settings;
+        match field {
+            Field::Name => return,
+            Field::Wave => {
+                if up && self.formation + 1 == self.formations.len() {
+                    if self.formations[self.formation].is_empty() {
+                        return;
+                    }
+                    self.formations.push(BTreeMap::new());
+                }
+                self.formation = if up {
+                    self.formation + 1
+                } else {
+                    self.formation.saturating_sub(1)
+                };
+                return;
+            }
+            Field::March => settings.march = step_millis(settings.march, up, 50),
+            Field::Fastest => {
+                let curve = settings.curve.get_or_insert(default_curve);
+                curve.fastest = step_value(curve.fastest, up, 0.05, 0.05, 1.0);
+            }
+            Field::Exponent => {
+                let curve = settings.curve.get_or_insert(default_curve);
+                curve.exponent = step_value(curve.exponent, up, 0.1, 0.1, 5.0);
+            }
+            // The difficulty's rate, then off, then ever slower bombs
+            Field::Bombs => {
+                settings.bombs = match (settings.bombs, up) {
+                    (None, true) => Some(None),
+                    (Some(None), true) => Some(Some(Duration::from_millis(250))),
+                    (Some(None), false) => None,
+                    (Some(rate), _) => match step_millis(rate, up, 250) {
+                        None => Some(None),
+                        rate => Some(rate),
+                    },
+                    (None, false) => None,
+                }
+            }
+            // Classic is what a level without a pattern does, so it isn't written out
+            Field::Moves => {
+                let patterns = Pattern::ALL;
+                let current = settings.pattern.unwrap_or_default();
+                let index = patterns.iter().position(|&p| p == current).unwrap_or(0);
+                let pattern = patterns[if up {
+                    (index + 1).min(patterns.len() - 1)
+                } else {
+                    index.saturating_sub(1)
+                }];
+                settings.pattern = (pattern != Pattern::Classic).then_some(pattern);
+            }
+        }
+        self.dirty = true;
+    }
+
+    // A blank frame for the playfield and the panel beside it
+    pub fn screen(&self) -> Frame {
+        sized_frame(self.width + PANEL_WIDTH, self.height)
+    }
+
+    // A frame the size of the playfield, to playtest on
+    pub fn playfield(&self) -> Frame {
+        sized_frame(self.width, self.height)
+    }
+
+    fn panel(&self) -> Vec<String> {
+        let file = self.level_file();
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let value = |field: Field| match field {
+            Field::Name => format!("Name  {}", file.name),
+            Field::Wave => format!("Wave  {}/{}", self.formation + 1, self.formations.len()),
+            Field::March => match file.march {
+                Some(march) => format!("March {}ms", march.as_millis()),
+                None => String::from("March auto"),
+            },
+            Field::Fastest => match file.curve {
+                Some(curve) => format!("Speed {}", curve.fastest),
+                None => String::from("Speed auto"),
+            },
+            Field::Exponent => match file.curve {
+                Some(curve) => format!("Curve {}", curve.exponent),
+                None => String::from("Curve auto"),
+            },
+            Field::Bombs => match file.bombs {
+                Some(Some(rate)) => format!("Bombs {}ms", rate.as_millis()),
+                Some(None) => String::from("Bombs off"),
+                None => String::from("Bombs difficulty"),
+            },
+            Field::Moves => format!("Moves {}", file.pattern.unwrap_or_default().name()),
+        };
+        let mut lines = vec![
+            format!("EDIT {}", name),
+            format!("LEVEL {:0>2}", self.level),
+            String::new(),
+        ];
+        for (index, &field) in FIELDS.iter().enumerate() {
+            let marker = if self.field == Some(index) { '>' } else { ' ' };
+            lines.push(format!("{}{}", marker, value(field)));
+        }
+        let threat = if file.formations.is_empty() {
+            0
+        } else {
+            Wave::authored(self.level, Arc::new(file.clone())).budget
+        };
+        lines.extend([
+            String::new(),
+            format!("Invaders {}", self.formations[self.formation].len()),
+            format!("Threat {}", threat),
+            format!("Bunker blocks {}", self.bunkers.len()),
+            format!("Timed events {}", file.cues.len()),
+            String::new(),
+            String::from("x soldier  X elite"),
+            String::from("H armoured"),
+            String::from("# bunker   Space erase"),
+            String::from("Tab panel  p playtest"),
+            String::from("s save     q quit"),
+            String::new(),
+            self.status.clone(),
+        ]);
+        lines
+    }
+}
+
+impl Drawable for Editor {
+    fn draw(&self, frame: &mut Frame) {
+        for &(x, y) in self.bunkers.iter() {
+            draw_text(frame, x, y, "#");
+        }
+        for (&(x, y), &kind) in self.formations[self.formation].iter() {
+            let glyph = match kind {
+                Kind::Elite => "X",
+                Kind::Armoured => "H",
+                _ => "x",
+            };
+            draw_text(frame, x, y, glyph);
+        }
+        draw_text(frame, self.width / 2, self.height.saturating_sub(3), "A");
+        draw_text(frame, self.cursor.0, self.cursor.1, "@");
+
+        for y in 0..self.height {
+            draw_text(frame, self.width, y, "|");
+        }
+        for (y, line) in self.panel().iter().enumerate() {
+            draw_text(frame, self.width + 2, y, line);
+        }
+    }
+}
+
+// One step of a millisecond setting, dropping back to unset below the first step
+fn step_millis(value: Option<Duration>, up: bool, step: u64) -> Option<Duration> {
+    let millis = value.map_or(0, |value| value.as_millis() as u64);
+    let millis = if up {
+        millis + step
+    } else {
+        millis.saturating_sub(step)
+    };
+    (millis > 0).then(|| Duration::from_millis(millis))
+}
+
+fn step_value(value: f32, up: bool, step: f32, min: f32, max: f32) -> f32 {
+    let value = if up { value + step } else { value - step };
+    // Round off the float error that repeated steps pile up
+    ((value * 100.0).round() / 100.0).clamp(min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> Editor {
+        let file = LevelFile::parse(text).unwrap();
+        Editor::new(PathBuf::from("level03.txt"), 3, file, &sized_frame(40, 20))
+    }
+
+    #[test]
+    fn should_keep_a_level_unchanged_when_opened_and_saved() {
+        // given
+        let text = "name Wall\nbombs off\nformation\nx.x\n.X.\nend\nformation\nXX\nend\nbunkers\n#.#\n###\nend\n";
+        // when
+        let editor = editor(text);
+        // then
+        assert_eq!(LevelFile::parse(text).unwrap(), editor.level_file());
+    }
+
+    #[test]
+    fn should_place_and_erase_at_the_cursor() {
+        // given
+        let mut editor = editor("formation\nx\nend\n");
+        editor.cursor = (10, 3);
+        // when
+        editor.press(KeyCode::Char('X'));
+        editor.press(KeyCode::Down);
+        editor.press(KeyCode::Char('#'));
+        editor.press(KeyCode::Left);
+        editor.press(KeyCode::Char('#'));
+        editor.press(KeyCode::Char(' '));
+        // then
+        let file = editor.level_file();
+        assert_eq!(2, file.formations[0].len());
+        assert!(file.formations[0].contains(&Spot {
+            x: 0,
+            y: 1,
+            kind: Kind::Elite
+        }));
+        assert_eq!(vec![(0, 0)], file.bunkers);
+    }
+
+    #[test]
+    fn should_edit_settings_in_the_panel_and_add_formations() {
+        // given
+        let mut editor = editor("formation\nx\nend\n");
+        // when
+        editor.press(KeyCode::Tab);
+        editor.press(KeyCode::Backspace);
+        editor.press(KeyCode::Char('Q'));
+        editor.press(KeyCode::Down);
+        editor.press(KeyCode::Right);
+        editor.press(KeyCode::Tab);
+        editor.press(KeyCode::Char('x'));
+        editor.press(KeyCode::Tab);
+        editor.press(KeyCode::Down);
+        editor.press(KeyCode::Down);
+        editor.press(KeyCode::Right);
+        editor.press(KeyCode::Right);
+        // then
+        let file = editor.level_file();
+        assert_eq!("Q", file.name);
+        assert_eq!(2, file.formations.len());
+        assert_eq!(Some(Duration::from_millis(100)), file.march);
+        assert_eq!(Some(Request::Playtest), editor.press(KeyCode::Char('p')));
+        assert_eq!(None, editor.press(KeyCode::Char('q')));
+        assert_eq!(Some(Request::Quit), editor.press(KeyCode::Char('q')));
+    }
+}
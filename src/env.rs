@@ -0,0 +1,303 @@
+// Gym-style training environment: `reset(seed)`, then `step(actions)` until done.
+// Runs the simulation on a fixed timestep with no terminal, audio or sleeping, so
+// agents can take thousands of steps per second.
+use crate::{
+    controller::Action,
+    difficulty::{Difficulty, DifficultyLevel},
+    frame::{sized_frame, Drawable, Frame},
+    game::{Game, State},
+    rng::Rng,
+    shot::Owner,
+};
+use std::time::Duration;
+
+// Cell codes used in grid observations
+pub const EMPTY: u8 = 0;
+pub const PLAYER: u8 = 1;
+pub const INVADER: u8 = 2;
+pub const SHOT: u8 = 3;
+pub const EXPLOSION: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObservationKind {
+    Grid,
+    Entities,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub width: usize,
+    pub height: usize,
+    pub difficulty: DifficultyLevel,
+    pub observation: ObservationKind,
+    pub tick: Duration, // Simulated time per step
+    pub max_steps: u64, // Episode is cut off after this many steps
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 20,
+            difficulty: DifficultyLevel::Normal,
+            observation: ObservationKind::Grid,
+            tick: Duration::from_millis(16),
+            max_steps: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entities {
+    pub player_x: usize,
+    pub invaders: Vec<(usize, usize)>, // Visible invaders only, plus the boss's cells
+    pub shots: Vec<(usize, usize)>, // The ship's own shots
+    pub bombs: Vec<(usize, usize)>, // Invader and boss bombs, to dodge
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Observation {
+    // Row-major cells of `width * height`, see the cell codes above
+    Grid {
+        width: usize,
+        height: usize,
+        cells: Vec<u8>,
+    },
+    Entities(Entities),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Info {
+    pub score: u32,
+    pub level: u16,
+    pub steps: u64,
+    pub state: State,
+    pub truncated: bool, // Done because `max_steps` ran out rather than the game ending
+}
+
+pub struct Env {
+    config: EnvConfig,
+    frame: Frame,
+    game: Game,
+    steps: u64,
+}
+
+impl Env {
+    pub fn new(config: EnvConfig) -> Self {
+        let frame = sized_frame(config.width, config.height);
+        let game = Game::new(&Difficulty::new(config.difficulty), Rng::new(0), &frame);
+        Self {
+            config,
+            frame,
+            game,
+            steps: 0,
+        }
+    }
+
+    // Start a new episode
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new(
+            &Difficulty::new(self.config.difficulty),
+            Rng::new(seed),
+            &self.frame,
+        );
+        self.steps = 0;
+        self.observe()
+    }
+
+    // Advance one tick; the reward is the points scored during it
+    pub fn step(&mut self, actions: &[Action]) -> (Observation, f32, bool, Info) {
+        let tick = self
+            .game
+            .step(self.config.tick, &[actions.to_vec()], &self.frame);
+        self.steps += 1;
+        let truncated = tick.state == State::Playing && self.steps >= self.config.max_steps;
+        let info = Info {
+            score: self.game.score.points(),
+            level: self.game.level.level(),
+            steps: self.steps,
+            state: tick.state,
+            truncated,
+        };
+        let done = tick.state != State::Playing || truncated;
+        (self.observe(), tick.points as f32, done, info)
+    }
+
+    // The underlying game, e.g. to drive it with a `Controller`
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    fn observe(&self) -> Observation {
+        match self.config.observation {
+            ObservationKind::Grid => self.grid(),
+            ObservationKind::Entities => Observation::Entities(self.entities()),
+        }
+    }
+
+    fn grid(&self) -> Observation {
+        // Draw just the playfield, leaving out the HUD text
+        let mut frame = sized_frame(self.config.width, self.config.height);
+        for player in self.game.players.iter() {
+            player.draw(&mut frame);
+        }
+        self.game.invaders.draw(&mut frame);
+        self.game.projectiles.draw(&mut frame);
+        if let Some(boss) = self.game.boss.as_ref() {
+            // The boss's cells count as invaders, its laser as a shot
+            for (x, y) in boss.cells() {
+                frame[x][y] = 'X';
+            }
+            if let Some(x) = boss.laser() {
+                let bottom = self.config.height.saturating_sub(1);
+                for cell in frame[x][boss.bottom().min(bottom)..bottom].iter_mut() {
+                    *cell = '|';
+                }
+            }
+        }
+
+        let mut cells = Vec::with_capacity(self.config.width * self.config.height);
+        for y in 0..self.config.height {
+            for column in frame.iter() {
+                cells.push(match column[y] {
+                    'A' | 'W' => PLAYER,
+                    'x' | '+' | 'X' | '%' | 'H' | 'h' | 'n' => INVADER,
+                    '|' | '!' => SHOT,
+                    '*' => EXPLOSION,
+                    _ => EMPTY,
+                });
+            }
+        }
+        Observation::Grid {
+            width: self.config.width,
+            height: self.config.height,
+            cells,
+        }
+    }
+
+    fn entities(&self) -> Entities {
+        Entities {
+            player_x: self.game.players[0].x(),
+            invaders: self
+                .game
+                .invaders
+                .iter()
+                .filter(|invader| invader.is_visible)
+                .map(|invader| (invader.x, invader.y))
+                .chain(self.game.boss.iter().flat_map(|boss| boss.cells()))
+                .collect(),
+            shots: self
+                .game
+                .projectiles
+                .iter()
+                .filter(|shot| shot.owner == Owner::Ship(0))
+                .map(|shot| (shot.x, shot.y))
+                .collect(),
+            bombs: self
+                .game
+                .projectiles
+                .iter()
+                .filter(|shot| !matches!(shot.owner, Owner::Ship(_)))
+                .map(|shot| (shot.x, shot.y))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shot::{Shot, Velocity, BOMB_INTERVAL};
+
+    fn run(env: &mut Env, seed: u64, actions: &[Action]) -> (Observation, f32) {
+        env.reset(seed);
+        let mut total = 0.0;
+        let mut last = None;
+        for i in 0..2000 {
+            let (observation, reward, done, _) = env.step(&actions[i % actions.len()..][..1]);
+            total += reward;
+            last = Some(observation);
+            if done {
+                break;
+            }
+        }
+        (last.unwrap(), total)
+    }
+
+    #[test]
+    fn should_replay_identically_for_same_seed() {
+        // given
+        let mut env = Env::new(EnvConfig::default());
+        let actions = [
+            Action::Fire,
+            Action::MoveLeft,
+            Action::Fire,
+            Action::MoveRight,
+        ];
+        // when
+        let first = run(&mut env, 7, &actions);
+        let second = run(&mut env, 7, &actions);
+        // then
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn should_observe_player_in_grid() {
+        // given
+        let mut env = Env::new(EnvConfig::default());
+        // when
+        let observation = env.reset(1);
+        // then
+        match observation {
+            Observation::Grid {
+                width,
+                height,
+                cells,
+            } => {
+                assert_eq!(width * height, cells.len());
+                assert_eq!(PLAYER, cells[17 * width + 20]);
+            }
+            _ => panic!("expected a grid observation"),
+        }
+    }
+
+    #[test]
+    fn should_truncate_after_max_steps() {
+        // given
+        let mut env = Env::new(EnvConfig {
+            observation: ObservationKind::Entities,
+            max_steps: 3,
+            ..EnvConfig::default()
+        });
+        env.reset(1);
+        // when
+        env.step(&[]);
+        env.step(&[]);
+        let (_, _, done, info) = env.step(&[]);
+        // then
+        assert!(done);
+        assert!(info.truncated);
+    }
+
+    #[test]
+    fn should_observe_falling_bombs() {
+        // given
+        let mut env = Env::new(EnvConfig {
+            observation: ObservationKind::Entities,
+            ..EnvConfig::default()
+        });
+        env.reset(1);
+        let bomb = Shot::new(5, 8, Owner::Invader, Velocity::down(BOMB_INTERVAL));
+        env.game.projectiles.fire(bomb);
+        // when
+        let observation = env.observe();
+        // then
+        match observation {
+            Observation::Entities(entities) => {
+                assert_eq!(vec![(5, 8)], entities.bombs);
+                assert!(entities.shots.is_empty());
+            }
+            _ => panic!("expected an entities observation"),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Typed events emitted by the simulation, so audio, scoring, statistics and effects
+// can react to what happened without the game knowing about any of them
+use crate::{invaders::Kind, powerup::PowerUp};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    ShotFired {
+        ship: usize,
+    },
+    InvaderKilled {
+        ship: usize, // Ship whose shot made the kill
+        kind: Kind,
+        points: u32,
+        pos: (usize, usize),
+    },
+    InvaderDamaged {
+        ship: usize,
+        kind: Kind,
+        health: u8, // Hits it takes yet
+        pos: (usize, usize),
+    },
+    ArmyStepped,   // The army marched one column sideways
+    ArmyDescended, // The army hit an edge and dropped a row
+    LevelCleared {
+        level: u16, // The level just reached
+    },
+    PlayerHit {
+        lives: u8, // Lives left afterwards
+    },
+    PowerUpCollected {
+        ship: usize,
+        kind: PowerUp,
+    },
+    PowerUpExpired {
+        ship: usize,
+        kind: PowerUp,
+    },
+    ShieldBroken {
+        ship: usize, // Ship whose shield took the invasion instead of a life
+    },
+    BossHit {
+        ship: usize,
+        health: u16,         // Left afterwards
+        phase_changed: bool, // The hit made the boss angrier
+    },
+    BossDefeated {
+        ship: usize, // Ship whose shot finished it
+        points: u32,
+    },
+}
+
+// Anything that reacts to game events
+pub trait Subscriber {
+    fn notify(&mut self, event: &GameEvent);
+}
+
+// Hand each event, in order, to every subscriber
+pub fn dispatch(events: &[GameEvent], subscribers: &mut [&mut dyn Subscriber]) {
+    for event in events {
+        for subscriber in subscribers.iter_mut() {
+            subscriber.notify(event);
+        }
+    }
+}
+
+// Running totals of shots and kills, for accuracy
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Tally {
+    pub shots: u32,
+    pub kills: u32,
+}
+
+impl Subscriber for Tally {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::ShotFired { .. } => self.shots += 1,
+            GameEvent::InvaderKilled { .. } | GameEvent::BossDefeated { .. } => self.kills += 1,
+            _ => {}
+        }
+    }
+}
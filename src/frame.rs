@@ -6,15 +6,37 @@ pub type Frame = Vec<Vec<char>>;
 pub fn new_frame() -> Frame {
     // Get terminal size
     let (term_width, term_height) = size().unwrap();
+    fit_frame(term_width, term_height)
+}
 
+// Create a frame that fits a terminal of the given size
+pub fn fit_frame(term_width: u16, term_height: u16) -> Frame {
     // Subtract some space for the border (if you're drawing a border around the game)
     let frame_width = term_width.saturating_sub(50); // Account for border or padding if needed
     let frame_height = term_height.saturating_sub(10);
 
     // Create a frame with dynamic rows and columns
-    let frame = vec![vec![' '; frame_height as usize]; frame_width as usize];
+    sized_frame(frame_width as usize, frame_height as usize)
+}
+
+// Create a blank frame of a fixed size, independent of the terminal
+pub fn sized_frame(width: usize, height: usize) -> Frame {
+    vec![vec![' '; height]; width]
+}
+
+// Draw a line of text, dropping whatever falls outside the frame
+pub fn draw_text(frame: &mut Frame, x: usize, y: usize, text: &str) {
+    for (i, c) in text.chars().enumerate() {
+        if let Some(cell) = frame.get_mut(x + i).and_then(|column| column.get_mut(y)) {
+            *cell = c;
+        }
+    }
+}
 
-    frame
+// Draw a line of text centred horizontally on row `y`
+pub fn draw_centered(frame: &mut Frame, y: usize, text: &str) {
+    let x = frame.len().saturating_sub(text.chars().count()) / 2;
+    draw_text(frame, x, y, text);
 }
 
 // Trait for drawable objects. They should implement a draw function that modifies the frame.
@@ -0,0 +1,696 @@
+use crate::{
+    boss::Boss,
+    controller::Action,
+    difficulty::Difficulty,
+    events::{dispatch, GameEvent, Tally},
+    frame::{draw_text, Drawable, Frame},
+    invaders::{Invaders, March},
+    level::Level,
+    pack::Special,
+    player::Player,
+    powerup::{Capsule, PowerUp, DROP_CHANCE},
+    rng::Rng,
+    score::Score,
+    shot::{Owner, Projectiles, Shot, Velocity, BOMB_INTERVAL},
+};
+use rusty_time::Timer;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum State {
+    #[default]
+    Playing,
+    Lost,
+    Won,
+}
+
+// What happened during a single step of the simulation: the events in order,
+// plus a summary of the ones the game's flow depends on
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Tick {
+    pub events: Vec<GameEvent>,
+    pub points: u32,
+    pub level_cleared: bool,
+    pub rows_cleared: u16, // Rows of the army wiped out this tick
+    pub life_lost: bool,
+    pub state: State,
+}
+
+// Lives each game starts with, as in the arcade original
+pub const LIVES: u8 = 3;
+
+// Ship glyphs in player order, so co-op players can tell their ships apart
+pub const SHIP_GLYPHS: [char; 2] = ['A', 'W'];
+
+// One game's worth of simulation, independent of the terminal and audio.
+// Co-op games have several ships sharing the wave, the lives and the level.
+pub struct Game {
+    pub players: Vec<Player>,
+    pub invaders: Invaders,
+    pub boss: Option<Boss>, // The mothership, on boss levels until it's destroyed
+    pub projectiles: Projectiles, // Every ship's shots and the invaders' bombs
+    pub score: Score,       // Combined score of every ship
+    pub level: Level,
+    pub lives: u8,
+    pub tally: Tally,
+    pub capsules: Vec<Capsule>,       // Power-ups falling toward the ships
+    pub bunkers: Vec<(usize, usize)>, // Blocks shielding the ships, on levels from a pack
+    pub rng: Rng,
+    drops: Rng, // Kept apart from `rng` so power-ups don't change the waves
+    bombs: Rng,
+    bomb_rate: Option<Duration>, // The difficulty's, unless a level file says otherwise
+    bomb_timer: Option<Timer>,   // Until the next invader bomb, on levels where they fire
+    clock: Duration,             // Time since the level (re)started, for its cues
+    cue: usize,                  // Next of the level's cues to happen
+}
+
+impl Game {
+    pub fn new(difficulty: &Difficulty, rng: Rng, frame: &Frame) -> Self {
+        Self::with_ships(1, difficulty, rng, frame)
+    }
+
+    // A game with `ships` players on the same keyboard, at most one per glyph
+    pub fn with_ships(ships: usize, difficulty: &Difficulty, rng: Rng, frame: &Frame) -> Self {
+        Self::starting_at(Level::new(), ships, difficulty, rng, frame)
+    }
+
+    // A game that begins at a later level, for practice
+    pub fn starting_at(
+        level: Level,
+        ships: usize,
+        difficulty: &Difficulty,
+        mut rng: Rng,
+        frame: &Frame,
+    ) -> Self {
+        let mut players: Vec<Player> = SHIP_GLYPHS[..ships.clamp(1, SHIP_GLYPHS.len())]
+            .iter()
+            .map(|&glyph| Player::with_glyph(difficulty, glyph))
+            .collect();
+        let count = players.len();
+        for (index, player) in players.iter_mut().enumerate() {
+            player.spread(frame, index, count);
+        }
+        let mut invaders = Invaders::new(difficulty);
+        invaders.populate(&level, frame, &mut rng);
+        let drops = rng.fork();
+        let bombs = rng.fork();
+        let mut game = Self {
+            players,
+            invaders,
+            boss: None,
+            projectiles: Projectiles::default(),
+            score: Score::new(),
+            level,
+            lives: LIVES,
+            tally: Tally::default(),
+            capsules: Vec::new(),
+            bunkers: Vec::new(),
+            rng,
+            drops,
+            bombs,
+            bomb_rate: difficulty.invader_fire_rate,
+            bomb_timer: None,
+            clock: Duration::ZERO,
+            cue: 0,
+        };
+        game.prepare_level(frame);
+        game
+    }
+
+    // Re-layout the playfield after the frame changed size
+    pub fn resize(&mut self, frame: &Frame) {
+        let count = self.players.len();
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.spread(frame, index, count);
+        }
+        self.invaders.populate(&self.level, frame, &mut self.rng);
+        self.prepare_level(frame);
+        self.capsules.clear();
+        self.projectiles.clear();
+    }
+
+    // Everything besides the invaders a level sets up: the boss, bunkers, bombs and cues
+    fn prepare_level(&mut self, frame: &Frame) {
+        self.boss = self
+            .invaders
+            .wave()
+            .boss
+            .then(|| Boss::new(self.level.level(), frame));
+        let file = self.invaders.wave().authored.clone();
+        self.bunkers.clear();
+        if let Some(file) = &file {
+            // Centered, with the bottom row just above the ships
+            let (width, height) = file.bunkers_size();
+            let left = (frame.len().saturating_sub(width) / 2).max(1);
+            let bottom = self.players[0].y().saturating_sub(2);
+            self.bunkers.extend(
+                file.bunkers
+                    .iter()
+                    .filter_map(|&(x, y)| Some((left + x, (bottom + y + 1).checked_sub(height)?)))
+                    .filter(|&(x, y)| x + 1 < frame.len() && y > 1),
+            );
+        }
+        let rate = file.and_then(|file| file.bombs).unwrap_or(self.bomb_rate);
+        self.bomb_timer = rate.map(Timer::new);
+        self.clock = Duration::ZERO;
+        self.cue = 0;
+    }
+
+    // Set off the level's cues that have come due
+    fn run_cues(&mut self, delta: Duration, frame: &Frame) {
+        self.clock += delta;
+        let Some(file) = self.invaders.wave().authored.clone() else {
+            return;
+        };
+        while let Some(cue) = file.cues.get(self.cue).filter(|cue| cue.at <= self.clock) {
+            match cue.special {
+                Special::Garbage(count) => {
+                    self.invaders.add_garbage(count, frame);
+                }
+                Special::PowerUp(kind) => {
+                    self.capsules.push(Capsule::new(kind, frame.len() / 2, 1));
+                }
+            }
+            self.cue += 1;
+        }
+    }
+
+    // Apply this tick's actions, one list per ship, then advance the simulation by `delta`
+    pub fn step(&mut self, delta: Duration, actions: &[Vec<Action>], frame: &Frame) -> Tick {
+        let mut tick = Tick::default();
+
+        for (ship, (player, actions)) in self.players.iter_mut().zip(actions).enumerate() {
+            for action in actions {
+                match action {
+                    Action::MoveLeft => player.move_left(frame),
+                    Action::MoveRight => player.move_right(frame),
+                    Action::Fire => {
+                        if player.shoot(Owner::Ship(ship), &mut self.projectiles) {
+                            tick.events.push(GameEvent::ShotFired { ship });
+                        }
+                    }
+                }
+            }
+        }
+
+        for (ship, player) in self.players.iter_mut().enumerate() {
+            for kind in player.update(delta) {
+                tick.events.push(GameEvent::PowerUpExpired { ship, kind });
+            }
+        }
+        self.run_cues(delta, frame);
+        self.drop_bombs(delta);
+        if let Some(boss) = self.boss.as_mut() {
+            boss.update(
+                delta,
+                frame,
+                &mut self.bombs,
+                &mut self.projectiles,
+                &mut self.invaders,
+            );
+        }
+        self.projectiles.update(delta, frame);
+        self.invaders.aim(
+            self.players
+                .iter()
+                .map(|player| (player.x(), player.y()))
+                .collect(),
+        );
+        match self.invaders.update(delta, frame) {
+            Some(March::Sideways) => tick.events.push(GameEvent::ArmyStepped),
+            Some(March::Down) => tick.events.push(GameEvent::ArmyDescended),
+            None => {}
+        }
+        // Invaders marching into a bunker flatten it
+        let invaders = &self.invaders;
+        self.bunkers.retain(|&(x, y)| invaders.at(x, y).is_none());
+        let rows = self.invaders.rows();
+        let bombed = self
+            .detect_hits(&mut tick)
+            .or_else(|| self.lasered())
+            .or_else(|| self.rammed());
+        tick.rows_cleared = (rows - self.invaders.rows()) as u16;
+        self.update_capsules(delta, frame, &mut tick);
+
+        // Win or lose conditions
+        if self.invaders.all_killed() && self.boss.is_none() {
+            if self.level.increment_level() {
+                tick.state = State::Won;
+            } else {
+                self.invaders.populate(&self.level, frame, &mut self.rng); // Next level's wave
+                self.prepare_level(frame);
+                tick.level_cleared = true;
+                tick.events.push(GameEvent::LevelCleared {
+                    level: self.level.level(),
+                });
+            }
+        } else if let Some(ship) = bombed {
+            self.disarm(ship);
+            if self.lose_life(ship, &mut tick) && tick.state == State::Playing {
+                self.respawn(frame);
+            }
+        } else if self.invaders.reached_bottom(frame) {
+            // Whichever ship has a shield left takes the invasion
+            let ship = self
+                .players
+                .iter()
+                .position(|player| player.has(PowerUp::Shield))
+                .unwrap_or(0);
+            self.lose_life(ship, &mut tick);
+            if tick.state == State::Playing {
+                self.resize(frame); // Only a landing restarts the level
+            }
+        }
+
+        self.finish(tick)
+    }
+
+    // A ship was hit or the army landed: a shield takes the hit in place of a life.
+    // Returns true if it cost a life
+    fn lose_life(&mut self, ship: usize, tick: &mut Tick) -> bool {
+        if self.players[ship].use_shield() {
+            tick.events.push(GameEvent::ShieldBroken { ship });
+            return false;
+        }
+        self.lives = self.lives.saturating_sub(1);
+        tick.life_lost = true;
+        tick.events.push(GameEvent::PlayerHit { lives: self.lives });
+        if self.lives == 0 {
+            tick.state = State::Lost;
+        }
+        true
+    }
+
+    // Put out whatever hit ship `ship` so it can't strike again on the next step:
+    // invaders that rammed it go down with it, and the boss's laser stops burning
+    fn disarm(&mut self, ship: usize) {
+        let (x, y) = (self.players[ship].x(), self.players[ship].y());
+        while self.invaders.hit_invader_at(x, y, u8::MAX).is_some() {}
+        if let Some(boss) = self.boss.as_mut() {
+            boss.cease_fire();
+        }
+    }
+
+    // Clear the air and put the ships back after a lost life, leaving the army as it is
+    fn respawn(&mut self, frame: &Frame) {
+        let count = self.players.len();
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.spread(frame, index, count);
+        }
+        self.projectiles.clear();
+    }
+
+    // Have a random invader in the front line drop a bomb whenever the bomb timer runs out
+    fn drop_bombs(&mut self, delta: Duration) {
+        let Some(timer) = self.bomb_timer.as_mut() else {
+            return;
+        };
+        timer.tick(delta);
+        if !timer.finished() {
+            return;
+        }
+        timer.reset();
+        let bombers = self.invaders.bombers();
+        if !bombers.is_empty() {
+            let (x, y) = bombers[self.bombs.below(bombers.len())];
+            let velocity = Velocity::down(BOMB_INTERVAL);
+            self.projectiles
+                .fire(Shot::new(x, y + 1, Owner::Invader, velocity));
+        }
+    }
+
+    // Resolve every shot against whatever it swept across since the last step,
+    // returning the ship a bomb hit, if any
+    fn detect_hits(&mut self, tick: &mut Tick) -> Option<usize> {
+        let mut bombed = None;
+        for shot in self.projectiles.iter_mut().filter(|shot| !shot.exploding) {
+            for (x, y) in shot.path().to_vec() {
+                // Bunkers take a block's worth of damage from anyone's shots
+                if let Some(block) = self.bunkers.iter().position(|&cell| cell == (x, y)) {
+                    self.bunkers.swap_remove(block);
+                    shot.explode_at(x, y);
+                    break;
+                }
+                match shot.owner {
+                    Owner::Ship(ship) => {
+                        // The boss stops every shot, piercing or not
+                        if let Some(boss) = self.boss.as_mut().filter(|boss| boss.covers(x, y)) {
+                            let phase_changed = boss.hit(shot.damage);
+                            tick.events.push(GameEvent::BossHit {
+                                ship,
+                                health: boss.health(),
+                                phase_changed,
+                            });
+                            if boss.defeated() {
+                                let points = boss.points();
+                                self.players[ship].credit(points);
+                                tick.points += points;
+                                tick.events.push(GameEvent::BossDefeated { ship, points });
+                                self.boss = None;
+                            }
+                            shot.explode_at(x, y);
+                            break;
+                        }
+                        let Some(hit) = self.invaders.hit_invader_at(x, y, shot.damage) else {
+                            continue;
+                        };
+                        // Armour that holds stops the shot, piercing or not
+                        if !hit.died {
+                            tick.events.push(GameEvent::InvaderDamaged {
+                                ship,
+                                kind: hit.kind,
+                                health: hit.health,
+                                pos: (hit.x, hit.y),
+                            });
+                            shot.explode_at(x, y);
+                            break;
+                        }
+                        self.players[ship].credit(hit.points);
+                        tick.points += hit.points;
+                        tick.events.push(GameEvent::InvaderKilled {
+                            ship,
+                            kind: hit.kind,
+                            points: hit.points,
+                            pos: (hit.x, hit.y),
+                        });
+                        if self.drops.chance(DROP_CHANCE) {
+                            let kind = PowerUp::ALL[self.drops.below(PowerUp::ALL.len())];
+                            self.capsules.push(Capsule::new(kind, hit.x, hit.y));
+                        }
+                        if !shot.piercing {
+                            shot.explode_at(x, y);
+                            break;
+                        }
+                    }
+                    Owner::Invader | Owner::Ufo => {
+                        let hit = self
+                            .players
+                            .iter()
+                            .position(|player| (player.x(), player.y()) == (x, y));
+                        if let Some(ship) = hit {
+                            bombed = bombed.or(Some(ship));
+                            shot.explode_at(x, y);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        bombed
+    }
+
+    // The ship standing in the boss's laser, if it's firing
+    fn lasered(&self) -> Option<usize> {
+        let column = self.boss.as_ref()?.laser()?;
+        self.players.iter().position(|player| player.x() == column)
+    }
+
+    // The ship an invader flew into, if any
+    fn rammed(&self) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| self.invaders.at(player.x(), player.y()).is_some())
+    }
+
+    // Let capsules fall, handing each to the ship that catches it
+    fn update_capsules(&mut self, delta: Duration, frame: &Frame, tick: &mut Tick) {
+        let height = frame[0].len();
+        let players = &mut self.players;
+        self.capsules.retain_mut(|capsule| {
+            capsule.update(delta);
+            let catcher = players
+                .iter()
+                .position(|player| player.x() == capsule.x && player.y() <= capsule.y);
+            match catcher {
+                Some(ship) => {
+                    players[ship].power_up(capsule.kind);
+                    tick.events.push(GameEvent::PowerUpCollected {
+                        ship,
+                        kind: capsule.kind,
+                    });
+                    false
+                }
+                None => capsule.y < height,
+            }
+        });
+    }
+
+    // Scoring and statistics keep themselves up to date from the events
+    fn finish(&mut self, tick: Tick) -> Tick {
+        dispatch(&tick.events, &mut [&mut self.score, &mut self.tally]);
+        tick
+    }
+}
+
+impl Drawable for Game {
+    fn draw(&self, frame: &mut Frame) {
+        for player in self.players.iter() {
+            player.draw(frame);
+        }
+        for capsule in self.capsules.iter() {
+            capsule.draw(frame);
+        }
+        for &(x, y) in self.bunkers.iter() {
+            frame[x][y] = '#';
+        }
+        self.projectiles.draw(frame);
+        let drawables: [&dyn Drawable; 2] = [&self.invaders, &self.level];
+        for drawable in drawables {
+            drawable.draw(frame);
+        }
+        if let Some(boss) = self.boss.as_ref() {
+            boss.draw(frame);
+        }
+        if self.players.len() > 1 {
+            // Each ship's own score, in place of the combined one
+            let scores: Vec<String> = self
+                .players
+                .iter()
+                .map(|player| format!("{}:{:0>4}", player.glyph(), player.points()))
+                .collect();
+            draw_text(frame, 0, 0, &scores.join(" "));
+        } else {
+            self.score.draw(frame);
+        }
+        draw_text(frame, 32, 0, &format!("LIVES: {}", self.lives));
+        let sub_waves = self.invaders.wave().sub_waves.len();
+        if sub_waves > 1 {
+            // Which of the level's sub-waves is on screen
+            let current = sub_waves - self.invaders.reinforcements();
+            draw_text(frame, 44, 0, &format!("WAVE: {}/{}", current, sub_waves));
+        }
+
+        // Active power-ups and the seconds they have left, along the bottom row
+        let mut active = Vec::new();
+        for player in self.players.iter() {
+            for effect in player.effects() {
+                let left = effect.timer.remaining().as_secs_f32().ceil();
+                let label = effect.kind.name().to_uppercase();
+                active.push(match self.players.len() {
+                    1 => format!("{} {}", label, left),
+                    _ => format!("{}:{} {}", player.glyph(), label, left),
+                });
+            }
+        }
+        if let Some(bottom) = frame.first().map(|column| column.len() - 1) {
+            draw_text(frame, 0, bottom, &active.join("  "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        frame::sized_frame,
+        invaders::Kind,
+        pack::{LevelFile, Pack},
+        shot::SHOT_INTERVAL,
+    };
+    use std::sync::Arc;
+
+    fn first_id(game: &Game) -> usize {
+        game.invaders.iter().next().unwrap().id
+    }
+
+    // Ready to fire, with ship `ship`'s next shot due to hit an invader after SHOT_INTERVAL
+    fn lined_up(ships: usize, ship: usize, frame: &Frame) -> Game {
+        let mut game = Game::with_ships(ships, &Difficulty::default(), Rng::new(1), frame);
+        game.step(Duration::from_secs(1), &[], frame); // Let the fire rate timer run out
+        let (x, y) = (game.players[ship].x(), game.players[ship].y());
+        let id = first_id(&game);
+        game.invaders.relocate(id, x, y - 2);
+        game
+    }
+
+    #[test]
+    fn should_credit_kills_to_the_ship_that_fired() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = lined_up(2, 1, &frame);
+        // when
+        game.step(Duration::ZERO, &[vec![], vec![Action::Fire]], &frame);
+        let points = game.step(SHOT_INTERVAL, &[], &frame).points;
+        // then
+        assert_eq!(1, points);
+        assert_eq!(0, game.players[0].points());
+        assert_eq!(points, game.players[1].points());
+        assert_eq!(points, game.score.points());
+    }
+
+    #[test]
+    fn should_emit_events_for_shot_and_kill() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = lined_up(1, 0, &frame);
+        let (x, y) = (game.players[0].x(), game.players[0].y());
+        // when
+        let fired = game.step(Duration::ZERO, &[vec![Action::Fire]], &frame);
+        let hit = game.step(SHOT_INTERVAL, &[], &frame);
+        // then
+        assert_eq!(vec![GameEvent::ShotFired { ship: 0 }], fired.events);
+        assert_eq!(
+            vec![GameEvent::InvaderKilled {
+                ship: 0,
+                kind: Kind::Soldier,
+                points: 1,
+                pos: (x, y - 2),
+            }],
+            hit.events
+        );
+        assert_eq!(Tally { shots: 1, kills: 1 }, game.tally);
+    }
+
+    #[test]
+    fn should_hit_invader_that_moved_across_the_shot() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = lined_up(1, 0, &frame);
+        let (x, y) = (game.players[0].x(), game.players[0].y());
+        let id = first_id(&game);
+        game.invaders.relocate(id, x, y - 4);
+        game.step(Duration::ZERO, &[vec![Action::Fire]], &frame);
+        // The shot is now just below the invader
+        game.step(SHOT_INTERVAL * 2, &[], &frame);
+        // when
+        game.invaders.relocate(id, x, y - 3); // The invader steps down onto it as it climbs past
+        let tick = game.step(SHOT_INTERVAL, &[], &frame);
+        // then
+        assert_eq!(1, tick.points);
+    }
+
+    #[test]
+    fn should_lay_out_pack_level_with_bunkers_that_stop_shots() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut pack = Pack::default();
+        pack.insert(
+            1,
+            LevelFile::parse("formation\nXxX\nend\nbunkers\n#\nend\n").unwrap(),
+        );
+        let level = Level::new().with_pack(Arc::new(pack));
+        let mut game = Game::starting_at(level, 1, &Difficulty::default(), Rng::new(1), &frame);
+        let y = game.players[0].y();
+        game.step(Duration::from_secs(1), &[vec![Action::MoveLeft]], &frame);
+        let bunkers = game.bunkers.clone();
+        // when
+        game.step(Duration::ZERO, &[vec![Action::Fire]], &frame);
+        game.step(SHOT_INTERVAL * 2, &[], &frame);
+        // then
+        assert_eq!(7, game.invaders.wave().budget);
+        assert_eq!(vec![(game.players[0].x(), y - 2)], bunkers);
+        assert!(game.bunkers.is_empty());
+        assert_eq!(3, game.invaders.remaining());
+    }
+
+    #[test]
+    fn should_pay_out_for_the_boss_and_clear_its_level() {
+        // given
+        let frame = sized_frame(40, 20);
+        let level = Level::starting_at(10);
+        let mut game = Game::starting_at(level, 1, &Difficulty::default(), Rng::new(1), &frame);
+        game.step(Duration::from_millis(500), &[], &frame);
+        let boss = game.boss.as_mut().unwrap();
+        while boss.health() > 1 {
+            boss.hit(1);
+        }
+        // Right over the ship
+        boss.x = game.players[0].x() - 4;
+        // when
+        game.step(Duration::ZERO, &[vec![Action::Fire]], &frame);
+        let tick = game.step(SHOT_INTERVAL * 14, &[], &frame);
+        // then
+        assert!(game.boss.is_none());
+        assert_eq!(100, tick.points);
+        assert!(tick.level_cleared);
+        assert_eq!(11, game.level.level());
+    }
+
+    #[test]
+    fn should_collect_power_up_and_let_it_expire() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        let (x, y) = (game.players[0].x(), game.players[0].y());
+        game.capsules.push(Capsule::new(PowerUp::Spread, x, y - 1));
+        // when
+        let caught = game.step(Duration::from_millis(150), &[], &frame);
+        let expired = game.step(PowerUp::Spread.duration(), &[], &frame);
+        // then
+        let collected = GameEvent::PowerUpCollected {
+            ship: 0,
+            kind: PowerUp::Spread,
+        };
+        assert!(caught.events.contains(&collected));
+        assert!(game.capsules.is_empty());
+        assert!(expired.events.contains(&GameEvent::PowerUpExpired {
+            ship: 0,
+            kind: PowerUp::Spread,
+        }));
+        assert!(!game.players[0].has(PowerUp::Spread));
+    }
+
+    #[test]
+    fn should_spend_shield_instead_of_a_life() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        game.players[0].power_up(PowerUp::Shield);
+        let id = first_id(&game);
+        game.invaders.relocate(id, 0, frame[0].len() - 1);
+        // when
+        let tick = game.step(Duration::ZERO, &[], &frame);
+        // then
+        assert_eq!(vec![GameEvent::ShieldBroken { ship: 0 }], tick.events);
+        assert_eq!(LIVES, game.lives);
+        assert!(!game.players[0].has(PowerUp::Shield));
+    }
+
+    #[test]
+    fn should_keep_the_army_when_a_ship_is_bombed() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        let (x, y) = game
+            .invaders
+            .iter()
+            .map(|invader| (invader.x, invader.y))
+            .next()
+            .unwrap();
+        game.invaders.hit_invader_at(x, y, u8::MAX); // Some progress that should survive
+        let remaining = game.invaders.remaining();
+        let home = game.players[0].x();
+        game.players[0].move_right(&frame);
+        let (x, y) = (game.players[0].x(), game.players[0].y());
+        let velocity = Velocity::down(BOMB_INTERVAL);
+        game.projectiles
+            .fire(Shot::new(x, y - 1, Owner::Invader, velocity));
+        // when
+        let tick = game.step(BOMB_INTERVAL, &[], &frame);
+        // then
+        assert!(tick.life_lost);
+        assert_eq!(LIVES - 1, game.lives);
+        assert_eq!(remaining, game.invaders.remaining());
+        assert_eq!(home, game.players[0].x());
+    }
+}
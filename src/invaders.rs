@@ -1,21 +1,185 @@
-use crate::{difficulty::Difficulty, frame::{Drawable, Frame}};
+use crate::{
+    difficulty::{Difficulty, DifficultyLevel, SpeedCurve},
+    frame::{Drawable, Frame},
+    level::Level,
+    pack::LevelFile,
+    pattern::Pattern,
+    rng::Rng,
+    wave::{SubWave, Wave, ARMOURED_COST, ELITE_COST},
+};
 use rusty_time::Timer;
-use std::{cmp::max, time::Duration};
+use std::{cmp::max, collections::VecDeque, time::Duration};
+
+// Fastest full-army step interval reachable through level progression, in milliseconds
+const MIN_LEVEL_SPEED: u64 = 100;
+// Fastest step interval at all, reached by the last few invaders
+const MIN_STEP_SPEED: u64 = 20;
+// How long a hit invader blinks out, letting the shot's burst show through
+const FLASH: Duration = Duration::from_millis(150);
+// On dive and kamikaze levels, an invader leaves the formation this often
+const SORTIE_EVERY: Duration = Duration::from_secs(3);
+// Time for an invader out of formation to fly one cell
+const FLIGHT_STEP: Duration = Duration::from_millis(120);
+// Invaders out of formation at once
+const MAX_SORTIES: usize = 2;
+// Phase the sway's sine wave advances by each army step, and across each column
+const SWAY_STEP: f32 = 0.7;
+const SWAY_COLUMN: f32 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Soldier,  // Part of the level's wave
+    Elite,    // Tougher member of later waves, worth more points
+    Garbage,  // Sent over by a versus opponent
+    Armoured, // Takes several hits, worth the most points
+}
+
+impl Kind {
+    // Points for destroying one, which is also its threat in a wave's budget
+    pub fn points(&self) -> u32 {
+        match self {
+            Kind::Elite => ELITE_COST,
+            Kind::Armoured => ARMOURED_COST,
+            Kind::Soldier | Kind::Garbage => 1,
+        }
+    }
+
+    // Hits it takes to destroy
+    pub fn health(&self) -> u8 {
+        match self {
+            Kind::Armoured => 3,
+            _ => 1,
+        }
+    }
+}
 
 pub struct Invader {
+    pub id: usize, // Stable for the whole wave, whoever else is destroyed
     pub x: usize,
     pub y: usize,
-    points: u16,
+    pub kind: Kind,
+    pub health: u8,
+    flash: Duration,      // Left of the blink after a hit that didn't destroy it
+    group: usize,         // Half of a split army it marches with; 0 otherwise
+    bob: usize,           // Rows the sway has pushed it down
     pub is_visible: bool, // Control visibility for the pop animation
 }
 
+impl Invader {
+    // A fresh invader, hidden until it pops in; its id is assigned once deployed
+    fn new(kind: Kind, x: usize, y: usize) -> Self {
+        Self {
+            id: 0,
+            x,
+            y,
+            kind,
+            health: kind.health(),
+            flash: Duration::ZERO,
+            group: 0,
+            bob: 0,
+            is_visible: false,
+        }
+    }
+}
+
+// A shot landing on an invader
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub kind: Kind,
+    pub died: bool,
+    pub points: u32, // Scored by the hit: the invader's worth if it died, else nothing
+    pub health: u8,  // Left afterwards
+    pub x: usize,
+    pub y: usize,
+}
+
+// How the army moved on a step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum March {
+    Sideways,
+    Down,
+}
+
+// Where an invader out of formation is headed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Flight {
+    Diving,    // Down at the column a ship was in when it left
+    Returning, // Back to its place in the formation
+    Homing,    // At whichever ship is nearest, until it crashes
+}
+
+struct Sortie {
+    id: usize,
+    flight: Flight,
+    home: (usize, usize), // Its place in the formation, marching along with the army
+    aim: usize,           // Column it dives at
+}
+
+// Which invader, if any, occupies each cell of the playfield, for O(1) hit tests.
+// Invaders out of formation can fly over others; the one already in a cell keeps it,
+// and the latecomer waits in `stacked` until the cell is free again.
+#[derive(Default)]
+struct Grid {
+    height: usize,
+    cells: Vec<Option<usize>>, // Column-major, like frames
+    stacked: Vec<usize>,       // Invaders sharing a cell with the one shown in `cells`
+}
+
+impl Grid {
+    // Empty every cell, only allocating again when the frame changed size
+    fn clear(&mut self, frame: &Frame) {
+        let height = frame.first().map_or(0, Vec::len);
+        if self.height != height || self.cells.len() != frame.len() * height {
+            self.height = height;
+            self.cells = vec![None; frame.len() * height];
+        } else {
+            self.cells.fill(None);
+        }
+        self.stacked.clear();
+    }
+
+    fn insert(&mut self, x: usize, y: usize, id: usize) {
+        if self.get(x, y).is_some() {
+            self.stacked.push(id);
+        } else {
+            self.set(x, y, Some(id));
+        }
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        let index = x * self.height + y;
+        (y < self.height && index < self.cells.len()).then_some(index)
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<usize> {
+        self.index(x, y).and_then(|index| self.cells[index])
+    }
+
+    fn set(&mut self, x: usize, y: usize, id: Option<usize>) {
+        if let Some(index) = self.index(x, y) {
+            self.cells[index] = id;
+        }
+    }
+}
+
 pub struct Invaders {
-    pub army: Vec<Invader>,
+    army: Vec<Option<Invader>>, // Indexed by invader id; destroyed invaders leave a gap
+    alive: usize,
+    grid: Grid,
+    reinforcements: VecDeque<Vec<Invader>>, // Sub-waves still to come this level
     pub total_count: usize,
     move_timer: Timer,
-    pop_timer: Timer, // Timer to control the pop animation
-    direction: i32,
-    level: u16,             // Add level to track which level we're on
+    base_speed: Duration, // The difficulty's invader speed, scaling the level's step interval
+    speed_curve: SpeedCurve, // Speed-up as the army thins out
+    pop_timer: Timer,     // Timer to control the pop animation
+    directions: [i32; 2], // Marching direction of each group, -1 for left and 1 for right
+    sorties: Vec<Sortie>, // Invaders out of formation
+    launch_timer: Timer,
+    flight_timer: Timer,
+    launches: usize, // Sorties sent so far, to take turns along the front line
+    sway_steps: u32, // Army steps since deployment, the phase of the sway
+    targets: Vec<(usize, usize)>, // Ships sorties go for
+    wave: Wave,      // The level's wave parameters, as handed over by `Level`
     invaders_popped: usize, // Track how many invaders have been made visible during pop animation
     pub shots_fired: u32,
 }
@@ -25,146 +189,477 @@ impl Invaders {
     pub fn new(difficulty: &Difficulty) -> Self {
         Self {
             army: Vec::new(),
+            alive: 0,
+            grid: Grid::default(),
+            reinforcements: VecDeque::new(),
             total_count: 0,
             move_timer: Timer::new(difficulty.invader_speed),
+            base_speed: difficulty.invader_speed,
+            speed_curve: difficulty.speed_curve,
             pop_timer: Timer::new(Duration::from_millis(200)), // Pop interval
-            direction: 1,
-            level: 1, // Start at level 1
+            directions: [1, -1],
+            sorties: Vec::new(),
+            launch_timer: Timer::new(SORTIE_EVERY),
+            flight_timer: Timer::new(FLIGHT_STEP),
+            launches: 0,
+            sway_steps: 0,
+            targets: Vec::new(),
+            wave: Level::new().wave(0),
             invaders_popped: 0,
             shots_fired: 0,
         }
     }
 
-    // Calculate the Fibonacci number for the given level
-    fn series(n: u16) -> u16 {
-        match n {
-            1 => 3,
-            2 => 5,
-            _ => {
-                let mut a = 3;
-                let mut b = 5;
-                for _ in 3..=n {
-                    let tmp = a + b;
-                    a = b;
-                    b = tmp;
-                }
-                b
-            }
-        }
-    }
-
-    // Populate invaders dynamically based on the current level and frame size
-    pub fn populate(&mut self, frame: &Frame) {
-        let frame_width = frame.len();
-        let frame_height = frame[0].len();
-
-        let num_invaders = Invaders::series(self.level) as usize; // Use the Fibonacci number directly
-
-        self.army.clear();
-        self.invaders_popped = 0;
-
+    // Formation spots in the upper half of the frame, back row first
+    fn slots(frame: &Frame) -> Vec<(usize, usize)> {
         let x_spacing = 3; // Space between invaders horizontally
         let y_spacing = 2; // Space between invaders vertically
+        let columns = frame.len().saturating_sub(4) / x_spacing;
+        let bottom = frame.first().map_or(0, Vec::len) / 2;
+        (2..bottom)
+            .step_by(y_spacing)
+            .flat_map(|y| (0..columns).map(move |column| (2 + column * x_spacing, y)))
+            .collect()
+    }
 
-        // Calculate how many invaders can fit in one row based on frame width
-        let invaders_per_row = (frame_width - 4) / x_spacing;
-
-        let mut invader_count = 0;
-        for row in 0.. {
-            let y_position = 2 + row * y_spacing;
+    // Populate the level's wave, keeping any sub-waves that don't fit on screen as reinforcements
+    pub fn populate(&mut self, level: &Level, frame: &Frame, rng: &mut Rng) {
+        let slots = Invaders::slots(frame);
+        let wave = level.wave(slots.len());
+        let layouts: Vec<Vec<Invader>> = match &wave.authored {
+            Some(file) => Invaders::authored(file, frame),
+            None => wave
+                .sub_waves
+                .iter()
+                .map(|sub_wave| Invaders::formation(sub_wave, &slots))
+                .collect(),
+        };
+        let mut groups: VecDeque<Vec<Invader>> = layouts
+            .into_iter()
+            .map(|mut group| {
+                rng.shuffle(&mut group); // Pop invaders in at random
+                group
+            })
+            .collect();
+        let first = groups.pop_front().unwrap_or_default();
+        self.reinforcements = groups;
+        self.wave = wave;
+        self.shots_fired = 0;
+        self.deploy(first, frame);
+    }
 
-            // Stop if we've reached beyond the available height in the frame
-            if y_position >= frame_height / 2 {
-                break;
-            }
+    // Line a sub-wave up on the formation spots, elites at the back and armour in front
+    fn formation(sub_wave: &SubWave, slots: &[(usize, usize)]) -> Vec<Invader> {
+        let size = sub_wave.size().min(slots.len());
+        slots
+            .iter()
+            .take(size)
+            .enumerate()
+            .map(|(index, &(x, y))| {
+                let kind = if index < sub_wave.elites {
+                    Kind::Elite
+                } else if index >= size.saturating_sub(sub_wave.armoured) {
+                    Kind::Armoured
+                } else {
+                    Kind::Soldier
+                };
+                Invader::new(kind, x, y)
+            })
+            .collect()
+    }
 
-            for col in 0..invaders_per_row {
-                if invader_count >= num_invaders {
-                    break; // Stop if we have placed all invaders
-                }
+    // A level file's formations, centered under the HUD. Invaders that don't fit the frame stay out.
+    fn authored(file: &LevelFile, frame: &Frame) -> Vec<Vec<Invader>> {
+        let (width, _) = file.formation_size();
+        let left = (frame.len().saturating_sub(width) / 2).max(1);
+        let bottom = frame.first().map_or(0, Vec::len).saturating_sub(5);
+        file.formations
+            .iter()
+            .map(|spots| {
+                spots
+                    .iter()
+                    .map(|spot| (left + spot.x, 2 + spot.y, spot.kind))
+                    .filter(|&(x, y, _)| x + 1 < frame.len() && y < bottom)
+                    .map(|(x, y, kind)| Invader::new(kind, x, y))
+                    .collect()
+            })
+            .collect()
+    }
 
-                let x_position = 2 + col * x_spacing;
+    // Put a sub-wave on screen, with the army's speed starting over from a full army
+    fn deploy(&mut self, mut group: Vec<Invader>, frame: &Frame) {
+        for (id, invader) in group.iter_mut().enumerate() {
+            invader.id = id;
+        }
+        // A split army's front rows march apart from the rows behind them
+        if self.wave.pattern == Pattern::Split {
+            let mut rows: Vec<usize> = group.iter().map(|invader| invader.y).collect();
+            rows.sort_unstable();
+            rows.dedup();
+            let front = rows.get(rows.len() / 2).copied().unwrap_or(0);
+            for invader in group.iter_mut() {
+                invader.group = (invader.y >= front) as usize;
+            }
+        }
+        self.army = group.into_iter().map(Some).collect();
+        self.alive = self.army.len();
+        self.total_count = self.alive;
+        self.invaders_popped = 0;
+        self.directions = [1, -1];
+        self.sorties.clear();
+        self.launch_timer.reset();
+        self.sway_steps = 0;
+        self.reindex(frame);
+        self.retime();
+    }
 
-                self.army.push(Invader {
-                    x: x_position,
-                    y: y_position,
-                    points: 1,
-                    is_visible: false,
-                });
+    // Sub-waves still waiting to arrive
+    pub fn reinforcements(&self) -> usize {
+        self.reinforcements.len()
+    }
 
-                invader_count += 1;
-            }
+    // What the current level sent
+    pub fn wave(&self) -> &Wave {
+        &self.wave
+    }
 
-            // Break out of the loop once all invaders are placed
-            if invader_count >= num_invaders {
-                break;
+    // Update invaders based on time elapsed and frame size, returning how the army moved if it did
+    pub fn update(&mut self, delta: Duration, frame: &Frame) -> Option<March> {
+        // Reinforcements arrive once the sub-wave before them is wiped out
+        if self.alive == 0 {
+            if let Some(group) = self.reinforcements.pop_front() {
+                self.deploy(group, frame);
             }
         }
 
-        self.total_count = self.army.len();
-    }
+        for invader in self.army.iter_mut().flatten() {
+            invader.flash = invader.flash.saturating_sub(delta);
+        }
 
-    // Update invaders based on time elapsed and frame size
-    pub fn update(&mut self, delta: Duration, frame: &Frame) -> bool {
         // Handle the pop animation by revealing invaders gradually
         self.pop_timer.tick(delta);
         if self.pop_timer.finished() {
             self.pop_timer.reset();
-            if self.invaders_popped < self.army.len() {
-                self.army[self.invaders_popped].is_visible = true;
+            // Destroyed invaders have nothing left to reveal, so skip their slots
+            while self.invaders_popped < self.army.len() {
                 self.invaders_popped += 1;
+                if let Some(invader) = &mut self.army[self.invaders_popped - 1] {
+                    invader.is_visible = true;
+                    break;
+                }
             }
         }
 
         // Handle movement
+        let mut march = None;
         self.move_timer.tick(delta);
         if self.move_timer.finished() {
             self.move_timer.reset();
-            let mut downwards = false;
-            let frame_width = frame.len();
-
-            if self.direction == -1 {
-                let min_x = self.army.iter().map(|invader| invader.x).min().unwrap_or(0);
-                if min_x == 1 {
-                    self.direction = 1;
-                    downwards = true;
+            march = Some(self.march(frame));
+            if self.wave.pattern == Pattern::Sway {
+                self.sway();
+            }
+            self.reindex(frame);
+        }
+        if self.wave.pattern.sorties() && self.fly(delta, frame) {
+            self.reindex(frame);
+        }
+        march
+    }
+
+    // Step each group sideways, or down a row once it reaches a wall. Only a split army
+    // has more than one group; invaders out of formation don't march.
+    fn march(&mut self, frame: &Frame) -> March {
+        let mut march = March::Sideways;
+        for group in 0..self.directions.len() {
+            let columns: Vec<usize> = self.formation_of(group).map(|invader| invader.x).collect();
+            let (Some(&min_x), Some(&max_x)) = (columns.iter().min(), columns.iter().max()) else {
+                continue;
+            };
+            let direction = self.directions[group];
+            let (dx, dy) =
+                if (direction < 0 && min_x <= 1) || (direction > 0 && max_x >= frame.len() - 2) {
+                    self.directions[group] = -direction;
+                    march = March::Down;
+                    (0, !self.blocked(group) as usize)
+                } else {
+                    (direction, 0)
+                };
+            let flying: Vec<usize> = self.sorties.iter().map(|sortie| sortie.id).collect();
+            for invader in self.army.iter_mut().flatten() {
+                if invader.group == group && !flying.contains(&invader.id) {
+                    invader.x = invader.x.saturating_add_signed(dx as isize);
+                    invader.y += dy;
                 }
-            } else {
-                let max_x = self.army.iter().map(|invader| invader.x).max().unwrap_or(0);
-                if max_x >= frame_width - 2 {
-                    self.direction = -1;
-                    downwards = true;
+            }
+            // Their places move along, for divers to return to
+            let army = &self.army;
+            for sortie in self.sorties.iter_mut() {
+                if army[sortie.id]
+                    .as_ref()
+                    .is_some_and(|invader| invader.group == group)
+                {
+                    sortie.home.0 = sortie.home.0.saturating_add_signed(dx as isize);
+                    sortie.home.1 += dy;
                 }
             }
+        }
+        march
+    }
+
+    // Invaders of a group still in formation
+    fn formation_of(&self, group: usize) -> impl Iterator<Item = &Invader> {
+        self.iter().filter(move |invader| {
+            invader.group == group && !self.sorties.iter().any(|sortie| sortie.id == invader.id)
+        })
+    }
+
+    // The back half of a split army can't drop into the front half's rows
+    fn blocked(&self, group: usize) -> bool {
+        let back = self.formation_of(0).map(|invader| invader.y).max();
+        let front = self.formation_of(1).map(|invader| invader.y).min();
+        group == 0 && matches!((back, front), (Some(back), Some(front)) if back + 1 >= front)
+    }
+
+    // Ripple a sine wave through the columns, pushing those at its crest a row down.
+    // Invaders in a column bob together, so rows two apart never run into each other.
+    fn sway(&mut self) {
+        self.sway_steps += 1;
+        let phase = self.sway_steps as f32 * SWAY_STEP;
+        for invader in self.army.iter_mut().flatten() {
+            let bob = (phase + invader.x as f32 * SWAY_COLUMN)
+                .sin()
+                .max(0.0)
+                .round() as usize;
+            invader.y = invader.y - invader.bob + bob;
+            invader.bob = bob;
+        }
+    }
+
+    // Ships that diving and kamikaze invaders go for
+    pub fn aim(&mut self, targets: Vec<(usize, usize)>) {
+        self.targets = targets;
+    }
 
-            if downwards {
-                let new_duration = self.calculate_speed();
-                self.move_timer
-                    .set_duration(Duration::from_millis(new_duration));
-                for invader in self.army.iter_mut() {
+    // Send invaders out of formation and fly them a cell at a time, returning whether any moved
+    fn fly(&mut self, delta: Duration, frame: &Frame) -> bool {
+        // Shot down on the way
+        let army = &self.army;
+        self.sorties.retain(|sortie| army[sortie.id].is_some());
+
+        self.launch_timer.tick(delta);
+        if self.launch_timer.finished() {
+            self.launch_timer.reset();
+            self.launch();
+        }
+
+        self.flight_timer.tick(delta);
+        if !self.flight_timer.finished() || self.sorties.is_empty() {
+            return false;
+        }
+        // Never slower than the army, or divers couldn't catch up with their places
+        self.flight_timer
+            .set_duration(FLIGHT_STEP.min(self.move_timer.duration() / 2));
+        self.flight_timer.reset();
+        let height = frame.first().map_or(0, Vec::len);
+        let ground = self
+            .targets
+            .first()
+            .map_or(height.saturating_sub(3), |&(_, y)| y);
+        let toward = |from: usize, to: usize| {
+            from.saturating_add_signed((to as isize - from as isize).signum())
+        };
+        for sortie in self.sorties.iter_mut() {
+            let Some(invader) = self.army[sortie.id].as_mut() else {
+                continue;
+            };
+            match sortie.flight {
+                Flight::Diving => {
+                    invader.x = toward(invader.x, sortie.aim);
                     invader.y += 1;
+                    if invader.y >= ground {
+                        sortie.flight = Flight::Returning;
+                    }
+                }
+                Flight::Returning => {
+                    invader.x = toward(invader.x, sortie.home.0);
+                    invader.y = toward(invader.y, sortie.home.1);
                 }
-            } else {
-                for invader in self.army.iter_mut() {
-                    invader.x = ((invader.x as i32) + self.direction) as usize;
+                Flight::Homing => {
+                    invader.x = toward(invader.x, nearest(&self.targets, invader.x));
+                    invader.y += 1;
                 }
             }
-            return true;
         }
-        false
+
+        // Kamikazes that missed crash into the ground; divers back home rejoin the army
+        let crashed: Vec<usize> = self
+            .sorties
+            .iter()
+            .filter(|sortie| sortie.flight == Flight::Homing)
+            .filter(|sortie| {
+                self.army[sortie.id]
+                    .as_ref()
+                    .is_some_and(|invader| invader.y > ground)
+            })
+            .map(|sortie| sortie.id)
+            .collect();
+        for &id in crashed.iter() {
+            self.army[id] = None;
+            self.alive -= 1;
+        }
+        if !crashed.is_empty() {
+            self.retime();
+        }
+        let army = &self.army;
+        self.sorties.retain(|sortie| {
+            army[sortie.id].as_ref().is_some_and(|invader| {
+                sortie.flight != Flight::Returning || (invader.x, invader.y) != sortie.home
+            })
+        });
+        true
+    }
+
+    // Send a front-line invader out, taking turns along the line
+    fn launch(&mut self) {
+        if self.sorties.len() >= MAX_SORTIES || self.targets.is_empty() {
+            return;
+        }
+        let line: Vec<(usize, usize, usize)> = self
+            .bombers()
+            .into_iter()
+            .filter_map(|(x, y)| self.at(x, y))
+            .filter(|invader| !self.sorties.iter().any(|sortie| sortie.id == invader.id))
+            .map(|invader| (invader.id, invader.x, invader.y))
+            .collect();
+        if line.is_empty() {
+            return;
+        }
+        let (id, x, y) = line[self.launches % line.len()];
+        self.launches += 1;
+        let flight = match self.wave.pattern {
+            Pattern::Kamikaze => Flight::Homing,
+            _ => Flight::Diving,
+        };
+        self.sorties.push(Sortie {
+            id,
+            flight,
+            home: (x, y),
+            aim: nearest(&self.targets, x),
+        });
+    }
+
+    // Step interval of a full army, based on level and shots fired (for levels > 10)
+    fn level_speed(&self) -> u64 {
+        // A level file's march speed stands as written
+        if let Some(march) = self.wave.authored.as_ref().and_then(|file| file.march) {
+            return march.as_millis() as u64;
+        }
+        let level = self.wave.level as u64;
+        let speed = if level > 10 {
+            // Base speed decreases with level, and speeds up with more shots fired
+            let base_speed = max(1000u64.saturating_sub(level * 50), MIN_LEVEL_SPEED);
+            let shots_speed_increase = self.shots_fired as u64 * 10;
+            max(
+                base_speed.saturating_sub(shots_speed_increase),
+                MIN_LEVEL_SPEED,
+            )
+        } else {
+            max(2000u64.saturating_sub(level * 200), 500)
+        };
+        // Normal marches exactly as above; other difficulties scale it by their invader speed
+        let normal = Difficulty::new(DifficultyLevel::Normal).invader_speed;
+        speed * self.base_speed.as_millis() as u64 / normal.as_millis() as u64
     }
 
-    // Calculate new speed based on level and shots fired (for levels > 10)
+    // Calculate new speed, speeding up along the difficulty's curve as invaders are destroyed
     fn calculate_speed(&self) -> u64 {
-        if self.level > 10 {
-            let base_speed = max(1000 - (self.level as u64 * 50), 100); // Base speed decreases with level
-            let shots_speed_increase = self.shots_fired as u64 * 10; // Speed up with more shots fired
-            max(base_speed - shots_speed_increase, 100)
+        let curve = self.wave.authored.as_ref().and_then(|file| file.curve);
+        let factor = curve
+            .unwrap_or(self.speed_curve)
+            .factor(self.alive, self.total_count);
+        max((self.level_speed() as f32 * factor) as u64, MIN_STEP_SPEED)
+    }
+
+    // Apply the current speed to the move timer without waiting out a slower step
+    fn retime(&mut self) {
+        let duration = Duration::from_millis(self.calculate_speed());
+        self.move_timer.set_duration(duration);
+        if self.move_timer.remaining() > duration {
+            self.move_timer.set_remaining(duration);
+        }
+    }
+
+    // Rebuild the occupancy grid after the army moved or the frame changed size
+    fn reindex(&mut self, frame: &Frame) {
+        self.grid.clear(frame);
+        for invader in self.army.iter().flatten() {
+            self.grid.insert(invader.x, invader.y, invader.id);
+        }
+    }
+
+    // Free a cell, handing it to an invader stacked there if there is one
+    fn vacate(&mut self, x: usize, y: usize) {
+        let army = &self.army;
+        let under = self.grid.stacked.iter().position(|&id| {
+            army[id]
+                .as_ref()
+                .is_some_and(|invader| (invader.x, invader.y) == (x, y))
+        });
+        let next = under.map(|index| self.grid.stacked.swap_remove(index));
+        self.grid.set(x, y, next);
+    }
+
+    // Invaders still alive, in the order they pop in
+    pub fn iter(&self) -> impl Iterator<Item = &Invader> {
+        self.army.iter().flatten()
+    }
+
+    // Number of invaders still alive
+    pub fn remaining(&self) -> usize {
+        self.alive
+    }
+
+    // The invader at a position, if any
+    pub fn at(&self, x: usize, y: usize) -> Option<&Invader> {
+        let id = self.grid.get(x, y)?;
+        self.army[id].as_ref()
+    }
+
+    // Move a single invader, keeping the grid in step, to set up tests
+    #[cfg(test)]
+    pub(crate) fn relocate(&mut self, id: usize, x: usize, y: usize) {
+        let Some(invader) = self.army.get_mut(id).and_then(Option::as_mut) else {
+            return;
+        };
+        let from = (invader.x, invader.y);
+        (invader.x, invader.y) = (x, y);
+        self.grid.stacked.retain(|&stacked| stacked != id);
+        if self.grid.get(from.0, from.1) == Some(id) {
+            self.vacate(from.0, from.1);
+        }
+        self.grid.insert(x, y, id);
+    }
+
+    // Which way an invader is marching, -1 for left and 1 for right, or 0 out of formation
+    pub fn heading(&self, invader: &Invader) -> i32 {
+        if self.sorties.iter().any(|sortie| sortie.id == invader.id) {
+            0
         } else {
-            max(2000 - (self.level as u64 * 200), 500)
+            self.directions[invader.group]
         }
     }
 
+    // Current time between army steps
+    pub fn step_interval(&self) -> Duration {
+        self.move_timer.duration()
+    }
+
+    // Time left until the army's next step
+    pub fn time_to_step(&self) -> Duration {
+        self.move_timer.remaining()
+    }
+
     // Track shots fired by the player
     pub fn record_shot(&mut self) {
         self.shots_fired += 1;
@@ -172,38 +667,102 @@ impl Invaders {
 
     // Check if all invaders are killed
     pub fn all_killed(&self) -> bool {
-        self.army.is_empty()
+        self.alive == 0 && self.reinforcements.is_empty()
     }
 
     // Check if any invader has reached the bottom of the frame
     pub fn reached_bottom(&self, frame: &Frame) -> bool {
         let frame_height = frame[0].len();
-        self.army
-            .iter()
-            .any(|invader| invader.y >= frame_height - 1)
+        self.iter().any(|invader| invader.y >= frame_height - 1)
     }
 
-    // Kill an invader at a specific position
-    pub fn kill_invader_at(&mut self, x: usize, y: usize) -> u16 {
-        if let Some(idx) = self
-            .army
-            .iter()
-            .position(|invader| (invader.x == x) && (invader.y == y))
-        {
-            let points = self.army[idx].points;
-            self.army.remove(idx);
-            points
+    // Deal `damage` to the invader at a position, destroying it once its health runs out
+    pub fn hit_invader_at(&mut self, x: usize, y: usize, damage: u8) -> Option<Hit> {
+        let id = self.grid.get(x, y)?;
+        let invader = self.army[id].as_mut()?;
+        invader.health = invader.health.saturating_sub(damage);
+        let mut hit = Hit {
+            kind: invader.kind,
+            died: invader.health == 0,
+            points: 0,
+            health: invader.health,
+            x,
+            y,
+        };
+        if hit.died {
+            self.army[id] = None;
+            self.vacate(x, y);
+            self.alive -= 1;
+            self.retime();
+            hit.points = hit.kind.points();
         } else {
-            0
+            invader.flash = FLASH;
         }
+        Some(hit)
     }
 
-    // Increment the level and repopulate invaders for the new level
-    pub fn next_level(&mut self, frame: &Frame) {
-        self.level += 1; // Move to the next level
-        self.shots_fired = 0;
-        self.populate(frame); // Repopulate invaders based on the new level
+    // The lowest visible invader of each column, the only ones with a clear line of fire
+    pub fn bombers(&self) -> Vec<(usize, usize)> {
+        let mut lowest: Vec<(usize, usize)> = Vec::new();
+        for invader in self.iter().filter(|invader| invader.is_visible) {
+            match lowest.iter_mut().find(|(x, _)| *x == invader.x) {
+                Some((_, y)) => *y = (*y).max(invader.y),
+                None => lowest.push((invader.x, invader.y)),
+            }
+        }
+        lowest
     }
+
+    // Number of distinct rows the army occupies
+    pub fn rows(&self) -> usize {
+        let mut rows: Vec<usize> = self.iter().map(|invader| invader.y).collect();
+        rows.sort_unstable();
+        rows.dedup();
+        rows.len()
+    }
+
+    // Drop extra invaders onto free spots of the top row, returning how many fit.
+    // Placement doesn't use the rng, so receiving garbage keeps waves in sync with the opponent.
+    pub fn add_garbage(&mut self, count: usize, frame: &Frame) -> usize {
+        let y = 1; // Just under the HUD
+        let offset = self.iter().next().map_or(2, |invader| invader.x % 3);
+        let free: Vec<usize> = (offset.max(1)..frame.len().saturating_sub(1))
+            .step_by(3)
+            .filter(|&x| self.at(x, y).is_none())
+            .take(count)
+            .collect();
+        for &x in free.iter() {
+            self.spawn(Kind::Garbage, x, y);
+        }
+        free.len()
+    }
+
+    // Add one invader to the army mid-level, returning false if the cell is taken
+    pub fn spawn(&mut self, kind: Kind, x: usize, y: usize) -> bool {
+        if self.at(x, y).is_some() {
+            return false;
+        }
+        let id = self.army.len();
+        self.grid.set(x, y, Some(id));
+        self.army.push(Some(Invader {
+            id,
+            is_visible: true,
+            ..Invader::new(kind, x, y)
+        }));
+        self.alive += 1;
+        self.total_count += 1;
+        self.retime();
+        true
+    }
+}
+
+// Column of the ship closest to `x`, or `x` itself with no ships to go for
+fn nearest(targets: &[(usize, usize)], x: usize) -> usize {
+    targets
+        .iter()
+        .map(|&(column, _)| column)
+        .min_by_key(|column| column.abs_diff(x))
+        .unwrap_or(x)
 }
 
 impl Default for Invaders {
@@ -214,17 +773,246 @@ impl Default for Invaders {
 
 impl Drawable for Invaders {
     fn draw(&self, frame: &mut Frame) {
-        for invader in self.army.iter() {
-            if invader.is_visible {
-                frame[invader.x][invader.y] = if (self.move_timer.remaining().as_secs_f32()
+        for invader in self.iter() {
+            if invader.is_visible && invader.flash.is_zero() {
+                let first_frame = (self.move_timer.remaining().as_secs_f32()
                     / self.move_timer.duration().as_secs_f32())
-                    > 0.5
-                {
-                    'x'
-                } else {
-                    '+'
+                    > 0.5;
+                frame[invader.x][invader.y] = match (invader.kind, first_frame) {
+                    // Armour shows its damage rather than marching in place
+                    (Kind::Armoured, _) => match invader.health {
+                        3.. => 'H',
+                        2 => 'h',
+                        _ => 'n',
+                    },
+                    (Kind::Elite, true) => 'X',
+                    (Kind::Elite, false) => '%',
+                    (_, true) => 'x',
+                    (_, false) => '+',
                 };
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Frame {
+        vec![vec![' '; 40]; 80]
+    }
+
+    #[test]
+    fn should_speed_up_as_invaders_are_destroyed() {
+        // given
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::new(), &frame(), &mut Rng::new(1));
+        let full = invaders.step_interval();
+        // when
+        while invaders.remaining() > 1 {
+            let (x, y) = invaders
+                .iter()
+                .map(|invader| (invader.x, invader.y))
+                .next()
+                .unwrap();
+            invaders.hit_invader_at(x, y, 1);
+        }
+        // then
+        assert_eq!(Duration::from_millis(1800), full);
+        assert_eq!(Duration::from_millis(270), invaders.step_interval());
+    }
+
+    #[test]
+    fn should_restore_full_speed_on_next_level() {
+        // given
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::new(), &frame(), &mut Rng::new(1));
+        let (x, y) = invaders
+            .iter()
+            .map(|invader| (invader.x, invader.y))
+            .next()
+            .unwrap();
+        invaders.hit_invader_at(x, y, 1);
+        let mut level = Level::new();
+        level.increment_level();
+        // when
+        invaders.populate(&level, &frame(), &mut Rng::new(1));
+        // then
+        assert_eq!(Duration::from_millis(1600), invaders.step_interval());
+    }
+
+    #[test]
+    fn should_keep_ids_and_grid_in_step_with_kills() {
+        // given
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::new(), &frame(), &mut Rng::new(1));
+        let ids: Vec<usize> = invaders.iter().map(|invader| invader.id).collect();
+        let first = invaders
+            .iter()
+            .next()
+            .map(|invader| (invader.x, invader.y))
+            .unwrap();
+        // when
+        let kill = invaders.hit_invader_at(first.0, first.1, 1);
+        let again = invaders.hit_invader_at(first.0, first.1, 1);
+        // then
+        assert!(kill.is_some());
+        assert_eq!(None, again);
+        assert_eq!(
+            ids[1..],
+            invaders
+                .iter()
+                .map(|invader| invader.id)
+                .collect::<Vec<_>>()
+        );
+        for invader in invaders.iter() {
+            assert_eq!(
+                Some(invader.id),
+                invaders.at(invader.x, invader.y).map(|found| found.id)
+            );
+        }
+    }
+
+    #[test]
+    fn should_hit_invaders_stacked_in_one_cell_in_turn() {
+        // given
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::new(), &frame(), &mut Rng::new(1));
+        let ids: Vec<usize> = invaders.iter().map(|invader| invader.id).take(2).collect();
+        invaders.relocate(ids[0], 5, 30);
+        invaders.relocate(ids[1], 5, 30);
+        // when
+        let hits: Vec<bool> = (0..3)
+            .map(|_| invaders.hit_invader_at(5, 30, 1).is_some())
+            .collect();
+        // then
+        assert_eq!(vec![true, true, false], hits);
+    }
+
+    #[test]
+    fn should_wear_armour_down_before_destroying_it() {
+        // given
+        let mut frame = frame();
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::new(), &frame, &mut Rng::new(1));
+        let soldiers = invaders.remaining();
+        invaders.spawn(Kind::Armoured, 5, 5);
+        // when
+        let first = invaders.hit_invader_at(5, 5, 1).unwrap();
+        invaders.draw(&mut frame);
+        let flashed = frame[5][5];
+        invaders.update(FLASH, &frame);
+        invaders.draw(&mut frame);
+        let worn = frame[5][5];
+        invaders.hit_invader_at(5, 5, 1);
+        let last = invaders.hit_invader_at(5, 5, 1).unwrap();
+        // then
+        assert_eq!((false, 0, 2), (first.died, first.points, first.health));
+        assert_eq!(' ', flashed);
+        assert_eq!('h', worn);
+        assert_eq!((true, ARMOURED_COST), (last.died, last.points));
+        assert_eq!(soldiers, invaders.remaining());
+    }
+
+    #[test]
+    fn should_send_reinforcements_once_a_sub_wave_is_cleared() {
+        // given
+        let frame = frame();
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::starting_at(31), &frame, &mut Rng::new(1));
+        let waiting = invaders.reinforcements();
+        // when
+        let first: Vec<(usize, usize)> = invaders
+            .iter()
+            .map(|invader| (invader.x, invader.y))
+            .collect();
+        for (x, y) in first {
+            invaders.hit_invader_at(x, y, 1);
+        }
+        let cleared = invaders.all_killed();
+        invaders.update(Duration::ZERO, &frame);
+        // then
+        assert!(waiting > 0);
+        assert!(!cleared);
+        assert_eq!(waiting - 1, invaders.reinforcements());
+        assert!(invaders.remaining() > 0);
+    }
+
+    // Positions by id, to compare before and after moving
+    fn positions(invaders: &Invaders) -> Vec<(usize, usize, usize)> {
+        invaders
+            .iter()
+            .map(|invader| (invader.id, invader.x, invader.y))
+            .collect()
+    }
+
+    #[test]
+    fn should_march_the_halves_of_a_split_army_apart() {
+        // given
+        let frame = frame();
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::starting_at(7), &frame, &mut Rng::new(1));
+        let before = positions(&invaders);
+        // when
+        invaders.update(invaders.step_interval(), &frame);
+        // then
+        let moves: Vec<(usize, i32)> = before
+            .iter()
+            .zip(positions(&invaders))
+            .map(|(&(_, x, y), (_, moved, _))| (y, moved as i32 - x as i32))
+            .collect();
+        let back = moves.iter().map(|&(y, _)| y).min().unwrap();
+        assert_eq!(Pattern::Split, invaders.wave().pattern);
+        assert!(invaders.rows() > 1);
+        assert!(moves
+            .iter()
+            .all(|&(y, dx)| dx == if y == back { 1 } else { -1 }));
+    }
+
+    #[test]
+    fn should_send_kamikazes_at_the_nearest_ship() {
+        // given
+        let frame = frame();
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::starting_at(9), &frame, &mut Rng::new(1));
+        invaders.aim(vec![(40, 37)]);
+        let count = invaders.remaining();
+        // when
+        invaders.update(SORTIE_EVERY, &frame);
+        let id = invaders.sorties[0].id;
+        let mut path = Vec::new();
+        while let Some(invader) = invaders.army[id].as_ref() {
+            path.push((invader.x, invader.y));
+            invaders.update(FLIGHT_STEP, &frame);
+        }
+        // then
+        assert_eq!(Some(&(40, 37)), path.last());
+        assert_eq!(count - 1, invaders.remaining());
+    }
+
+    #[test]
+    fn should_bring_divers_back_to_their_place() {
+        // given
+        let frame = frame();
+        let mut invaders = Invaders::default();
+        invaders.populate(&Level::starting_at(8), &frame, &mut Rng::new(1));
+        invaders.aim(vec![(40, 37)]);
+        let count = invaders.remaining();
+        // when
+        invaders.update(SORTIE_EVERY, &frame);
+        let id = invaders.sorties[0].id;
+        let mut deepest = 0;
+        while invaders.sorties.iter().any(|sortie| sortie.id == id) {
+            deepest = deepest.max(invaders.army[id].as_ref().unwrap().y);
+            invaders.update(FLIGHT_STEP, &frame);
+        }
+        // then
+        let diver = invaders.army[id].as_ref().unwrap();
+        assert_eq!(37, deepest);
+        assert_eq!(count, invaders.remaining());
+        assert!(invaders
+            .iter()
+            .any(|invader| invader.id != id && invader.y == diver.y));
+    }
+}
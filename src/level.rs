@@ -1,16 +1,63 @@
-use crate::frame::{Drawable, Frame};
+use crate::{
+    boss::BOSS_EVERY,
+    frame::{Drawable, Frame},
+    pack::Pack,
+    wave::Wave,
+};
+use std::sync::Arc;
 
-const MAX_LEVEL: u16 = 999;
+pub const MAX_LEVEL: u16 = 999;
 
+// Progress through the levels: the one place the current level is kept, deciding
+// each level's wave and when the game is won
+#[derive(Clone)]
 pub struct Level {
     level: u16,
+    pack: Option<Arc<Pack>>, // Hand-made levels, used where they exist
 }
 
 impl Level {
     pub fn new() -> Self {
-        Self { level: 1 }
+        Self::starting_at(1)
     }
 
+    // Skip ahead to a later level, for practice
+    pub fn starting_at(level: u16) -> Self {
+        Self {
+            level: level.clamp(1, MAX_LEVEL),
+            pack: None,
+        }
+    }
+
+    // Play the levels of a pack, falling back to generated waves for the rest
+    pub fn with_pack(mut self, pack: Arc<Pack>) -> Self {
+        self.pack = Some(pack);
+        self
+    }
+
+    pub fn level(&self) -> u16 {
+        self.level
+    }
+
+    // Every 10th level is a boss fight, unless a pack has its own level there
+    pub fn is_boss(&self) -> bool {
+        let authored = self
+            .pack
+            .as_ref()
+            .is_some_and(|pack| pack.get(self.level).is_some());
+        self.level.is_multiple_of(BOSS_EVERY) && !authored
+    }
+
+    // The current level's wave, on a screen with room for `capacity` invaders at once
+    pub fn wave(&self, capacity: usize) -> Wave {
+        match self.pack.as_ref().and_then(|pack| pack.get(self.level)) {
+            Some(file) => Wave::authored(self.level, file.clone()),
+            None if self.is_boss() => Wave::boss(self.level),
+            None => Wave::generate(self.level, capacity),
+        }
+    }
+
+    // Move on to the next level, returning true once the last one has been cleared
     pub fn increment_level(&mut self) -> bool {
         if self.level <= MAX_LEVEL {
             self.level += 1;
@@ -41,6 +88,7 @@ impl Drawable for Level {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pack::LevelFile;
 
     #[test]
     fn should_increment_level_and_return_false() {
@@ -65,4 +113,35 @@ mod tests {
         assert_eq!(false, actual);
         assert_eq!(4, level.level);
     }
+
+    #[test]
+    fn should_clamp_practice_start_to_real_levels() {
+        assert_eq!(1, Level::starting_at(0).level());
+        assert_eq!(250, Level::starting_at(250).level());
+        assert_eq!(MAX_LEVEL, Level::starting_at(5000).level());
+    }
+
+    #[test]
+    fn should_win_after_clearing_the_last_level() {
+        // given
+        let mut level = Level::starting_at(MAX_LEVEL);
+        // when
+        let won = level.increment_level();
+        // then
+        assert!(won);
+    }
+
+    #[test]
+    fn should_let_a_pack_level_replace_the_boss() {
+        // given
+        let mut pack = Pack::default();
+        pack.insert(10, LevelFile::parse("formation\nxxx\nend\n").unwrap());
+        // when
+        let generated = Level::starting_at(10);
+        let authored = Level::starting_at(10).with_pack(Arc::new(pack));
+        // then
+        assert!(generated.is_boss());
+        assert!(!authored.is_boss());
+        assert!(!authored.wave(100).boss);
+    }
 }
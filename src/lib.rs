@@ -1,12 +1,33 @@
+pub mod boss;
+pub mod cli;
+pub mod controller;
+pub mod daily;
 pub mod difficulty;
+pub mod editor;
+pub mod env;
+pub mod events;
 pub mod frame;
+pub mod game;
 pub mod invaders;
 pub mod level;
 pub mod menu;
+pub mod music;
+pub mod net;
+pub mod overlay;
+pub mod pack;
+pub mod pattern;
 pub mod player;
+pub mod powerup;
 pub mod render;
+pub mod rng;
 pub mod score;
+pub mod serve;
+pub mod session;
 pub mod shot;
+pub mod sim;
+pub mod spectate;
+pub mod synth;
+pub mod wave;
 
 pub const DEFAULT_ROWS: usize = 100;
 pub const DEFAULT_COLS: usize = 100;
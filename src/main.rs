@@ -7,74 +7,434 @@ use crossterm::{
 use rusty_audio::Audio;
 use std::{
     error::Error,
-    sync::mpsc::{self, Receiver},
+    io::{BufReader, Read},
+    net::TcpListener,
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
     time::{Duration, Instant},
     {io, thread},
 };
 
 use invaders::{
+    cli::{Command, Options},
+    controller::{Autopilot, Controller, Keyboard, Layout},
+    daily::{self, Challenge, Entry, Leaderboard},
     difficulty::{Difficulty, DifficultyLevel},
+    editor::{Editor, Request},
+    events::{dispatch, GameEvent, Subscriber},
     frame::{self, new_frame, Drawable, Frame},
-    invaders::Invaders,
+    game::{Game, State, Tick},
     level::Level,
     menu::Menu,
-    player::Player,
+    music::Heartbeat,
+    net::{self, Outcome, Versus},
+    overlay::{Exporter, Snapshot},
+    pack::Pack,
     render,
-    score::Score,
+    rng::Rng,
+    serve,
+    session::Session,
+    sim,
+    spectate::{self, Broadcaster, Update},
+    synth::SoundPack,
 };
 
-fn render_screen(render_rx: Receiver<Frame>, last_size: &mut (u16, u16)) {
+#[derive(PartialEq)]
+enum Mode {
+    Menu,
+    Playing,
+    Demo,                 // Attract mode: the autopilot plays while the menu sits idle
+    Results(Vec<String>), // Winner screen after a multiplayer session
+}
+
+fn render_screen(
+    render_rx: Receiver<Frame>,
+    last_size: &mut (u16, u16),
+    mut broadcaster: Option<Broadcaster>,
+) {
     let mut last_frame = frame::new_frame();
     let mut stdout = io::stdout();
 
     render::render(&mut stdout, &last_frame, &last_frame, true, last_size);
     while let Ok(curr_frame) = render_rx.recv() {
-        render::render(&mut stdout, &last_frame, &curr_frame, false, last_size);
+        // Clear what's left of a larger frame
+        let force = curr_frame.len() != last_frame.len()
+            || curr_frame.first().map(Vec::len) != last_frame.first().map(Vec::len);
+        render::render(&mut stdout, &last_frame, &curr_frame, force, last_size);
+        if let Some(broadcaster) = broadcaster.as_mut() {
+            broadcaster.publish(&curr_frame);
+        }
         last_frame = curr_frame;
     }
 }
 
-fn reset_game(
-    in_menu: &mut bool,
-    player: &mut Player,
-    invaders: &mut Invaders,
-    frame: &Frame,
-    difficulty: &Difficulty,
-) {
-    *in_menu = true;
-    *player = Player::new(difficulty); // Reapply difficulty to player
-    player.center(frame);
-    *invaders = Invaders::new(difficulty); // Reapply difficulty to invaders
-    invaders.populate(frame);
+// Show someone else's broadcast game, read-only, until it ends or we quit
+fn run_watch(
+    stream: Box<dyn Read + Send>,
+    render_tx: &mpsc::Sender<Frame>,
+) -> Result<(), Box<dyn Error>> {
+    // The reader blocks on the socket, so it hands frames over rather than rendering itself
+    let (frame_tx, frame_rx) = mpsc::channel();
+    let reader = thread::spawn(move || -> io::Result<()> {
+        let mut stream = BufReader::new(stream);
+        let mut frame = Frame::new();
+        while let Some(update) = Update::read(&mut stream)? {
+            update.apply(&mut frame);
+            if frame_tx.send(frame.clone()).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    loop {
+        while event::poll(Duration::default())? {
+            if let Event::Key(key_event) = event::read()? {
+                if let KeyCode::Esc | KeyCode::Char('q') = key_event.code {
+                    return Ok(());
+                }
+            }
+        }
+        match frame_rx.recv_timeout(Duration::from_millis(20)) {
+            Ok(frame) => {
+                let _ = render_tx.send(frame);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break, // Broadcast ended
+        }
+    }
+    reader.join().expect("spectator thread panicked")?;
+    Ok(())
+}
+
+// Wrap up a finished session: the menu with a summary for solo games, a winner screen otherwise
+fn game_over(
+    menu: &mut Menu,
+    session: &Session,
+    daily_run: &mut Option<Leaderboard>,
+) -> io::Result<Mode> {
+    menu.daily_info = daily_info(&Challenge::today())?;
+    if session.players.len() > 1 {
+        let mut lines = vec![match session.winner() {
+            Some(index) => format!("PLAYER {} WINS", index + 1),
+            None => String::from("DRAW"),
+        }];
+        lines.push(String::new());
+        for (index, game) in session.players.iter().enumerate() {
+            lines.push(format!(
+                "PLAYER {}  SCORE: {:0>4}  LEVEL: {:0>2}",
+                index + 1,
+                game.score.points(),
+                game.level.level()
+            ));
+        }
+        lines.push(String::new());
+        lines.push(String::from("PRESS ANY KEY"));
+        return Ok(Mode::Results(lines));
+    }
+
+    // Show the seed so the run can be replayed with `--seed`
+    let game = session.game();
+    let mut status = format!(
+        "GAME OVER  SCORE: {:0>4}  SEED: {}",
+        game.score.points(),
+        game.rng.seed()
+    );
+    if game.players.len() > 1 {
+        for player in game.players.iter() {
+            status += &format!("  {}: {:0>4}", player.glyph(), player.points());
+        }
+    }
+    if let Some((rank, total)) = record_daily(daily_run, game)? {
+        status += &format!("  DAILY RANK: {}/{}", rank, total);
+    }
+    menu.status = Some(status);
+    Ok(Mode::Menu)
+}
+
+// Put a finished daily attempt on the leaderboard, returning the player's rank and the board size
+fn record_daily(
+    daily_run: &mut Option<Leaderboard>,
+    game: &Game,
+) -> io::Result<Option<(usize, usize)>> {
+    daily::finish(
+        daily_run,
+        Entry {
+            name: daily::player_name(),
+            score: game.score.points(),
+            level: game.level.level(),
+        },
+    )
+}
+
+// Menu lines describing today's challenge and its leaderboard
+fn daily_info(challenge: &Challenge) -> io::Result<Vec<String>> {
+    let board = Leaderboard::load(&daily::data_dir(), &challenge.date)?;
+    let mut lines = vec![challenge.describe()];
+    if board.attempted(&daily::player_name()) {
+        lines.push(String::from("Already played today, practice only"));
+    }
+    for (rank, entry) in board.entries.iter().take(5).enumerate() {
+        lines.push(format!(
+            "{}. {:<12} {:0>4}  LEVEL {:0>2}",
+            rank + 1,
+            entry.name,
+            entry.score,
+            entry.level
+        ));
+    }
+    Ok(lines)
+}
+
+// Sound effects, driven by the game's events
+struct Effects<'a> {
+    audio: &'a mut Audio,
+    sounds: &'a SoundPack,
+    heartbeat: &'a mut Heartbeat,
+}
+
+impl Subscriber for Effects<'_> {
+    fn notify(&mut self, event: &GameEvent) {
+        if let Some(note) = self.heartbeat.beat(event) {
+            self.audio.play(note);
+        }
+        match event {
+            GameEvent::ShotFired { .. } => self.audio.play("pew"),
+            GameEvent::InvaderKilled { points, .. } => {
+                // Higher-value kills explode at a higher pitch
+                let step = points.saturating_sub(1) as usize;
+                self.audio.play(self.sounds.pitched("explode", step));
+            }
+            GameEvent::InvaderDamaged { .. } => self.audio.play(self.sounds.pitched("explode", 0)),
+            GameEvent::PlayerHit { lives } if *lives > 0 => self.audio.play("lose"),
+            GameEvent::PowerUpCollected { kind, .. } => self.audio.play(kind.name()),
+            GameEvent::ShieldBroken { .. } => self.audio.play("lose"),
+            GameEvent::BossHit { phase_changed, .. } => {
+                // A higher-pitched boom when it gets angrier
+                let step = if *phase_changed { 4 } else { 0 };
+                self.audio.play(self.sounds.pitched("explode", step));
+            }
+            GameEvent::BossDefeated { .. } => self.audio.play("win"),
+            _ => {}
+        }
+    }
+}
+
+// Sound for one step of the game being played, the marching heartbeat included
+fn play_effects(audio: &mut Audio, sounds: &SoundPack, heartbeat: &mut Heartbeat, tick: &Tick) {
+    dispatch(
+        &tick.events,
+        &mut [&mut Effects {
+            audio,
+            sounds,
+            heartbeat,
+        }],
+    );
+}
+
+// A versus match against a networked opponent, until one side is out or we quit
+fn run_versus(
+    mut versus: Versus,
+    audio: &mut Audio,
+    sounds: &SoundPack,
+    render_tx: &mpsc::Sender<Frame>,
+    exporter: &mut Option<Exporter>,
+) -> Result<(), Box<dyn Error>> {
+    // Keep the starting size: a resize would reshuffle our wave out of sync with the opponent's
+    let blank = new_frame();
+    let mut keyboard = Keyboard::new();
+    let mut heartbeat = Heartbeat::new();
+    let mut instant = Instant::now();
+    loop {
+        let delta = instant.elapsed();
+        instant = Instant::now();
+        let mut curr_frame = blank.clone();
+
+        while event::poll(Duration::default())? {
+            if let Event::Key(key_event) = event::read()? {
+                match key_event.code {
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        versus.quit();
+                        audio.play("lose");
+                        return Ok(());
+                    }
+                    code => {
+                        keyboard.press(code);
+                    }
+                }
+            }
+        }
+
+        let actions = [keyboard.actions(&versus.game, delta)];
+        let tick = versus.step(delta, &actions, &curr_frame);
+        play_effects(audio, sounds, &mut heartbeat, &tick);
+        if let Some(exporter) = exporter.as_mut() {
+            exporter.publish(Snapshot::new(&versus.game, tick.state))?;
+        }
+        versus.draw(&mut curr_frame);
+        let _ = render_tx.send(curr_frame);
+        thread::sleep(Duration::from_millis(1));
+        if tick.state != State::Playing {
+            break;
+        }
+    }
+
+    let banner = match versus.outcome {
+        Some(Outcome::Lost) => "YOU LOSE",
+        Some(Outcome::Disconnected) => "OPPONENT LEFT - YOU WIN",
+        _ => "YOU WIN",
+    };
+    audio.play(if versus.outcome == Some(Outcome::Lost) {
+        "lose"
+    } else {
+        "win"
+    });
+    loop {
+        let mut curr_frame = blank.clone();
+        versus.draw(&mut curr_frame);
+        let middle = curr_frame[0].len() / 2;
+        frame::draw_centered(&mut curr_frame, middle, banner);
+        frame::draw_centered(&mut curr_frame, middle + 2, "PRESS ANY KEY");
+        let _ = render_tx.send(curr_frame);
+        if let Event::Key(_) = event::read()? {
+            return Ok(());
+        }
+    }
+}
+
+// Edit a level file until we quit, playtesting it in between
+fn run_edit(
+    mut editor: Editor,
+    audio: &mut Audio,
+    sounds: &SoundPack,
+    render_tx: &mpsc::Sender<Frame>,
+    last_size: &mut (u16, u16),
+) -> Result<(), Box<dyn Error>> {
+    loop {
+        let size = crossterm::terminal::size()?;
+        if size != *last_size {
+            editor.resize(&new_frame());
+            *last_size = size;
+        }
+
+        while event::poll(Duration::default())? {
+            if let Event::Key(key_event) = event::read()? {
+                match editor.press(key_event.code) {
+                    Some(Request::Save) => editor.save(),
+                    Some(Request::Playtest) => {
+                        if let Some(level) = editor.playtest() {
+                            editor.status = run_playtest(level, audio, sounds, render_tx, &editor)?;
+                        }
+                    }
+                    Some(Request::Quit) => return Ok(()),
+                    None => {}
+                }
+            }
+        }
+
+        let mut curr_frame = editor.screen();
+        editor.draw(&mut curr_frame);
+        let _ = render_tx.send(curr_frame);
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+// Play the edited level in the normal game until it's cleared, lost or quit,
+// describing how it went
+fn run_playtest(
+    level: Level,
+    audio: &mut Audio,
+    sounds: &SoundPack,
+    render_tx: &mpsc::Sender<Frame>,
+    editor: &Editor,
+) -> Result<String, Box<dyn Error>> {
+    let blank = editor.playfield();
+    let number = level.level();
+    let mut game = Game::starting_at(level, 1, &Difficulty::default(), Rng::from_time(), &blank);
+    let mut keyboard = Keyboard::new();
+    let mut heartbeat = Heartbeat::new();
+    let mut instant = Instant::now();
+    loop {
+        let delta = instant.elapsed();
+        instant = Instant::now();
+        let mut curr_frame = blank.clone();
+
+        while event::poll(Duration::default())? {
+            if let Event::Key(key_event) = event::read()? {
+                match key_event.code {
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        return Ok(String::from("Playtest stopped"));
+                    }
+                    code => {
+                        keyboard.press(code);
+                    }
+                }
+            }
+        }
+
+        let actions = [keyboard.actions(&game, delta)];
+        let tick = game.step(delta, &actions, &curr_frame);
+        play_effects(audio, sounds, &mut heartbeat, &tick);
+        game.draw(&mut curr_frame);
+        let _ = render_tx.send(curr_frame);
+        thread::sleep(Duration::from_millis(1));
+
+        let score = game.score.points();
+        if game.level.level() != number || tick.state == State::Won {
+            audio.play("win");
+            return Ok(format!("Cleared, score {}", score));
+        }
+        if tick.state == State::Lost {
+            audio.play("lose");
+            return Ok(format!("Lost, score {}", score));
+        }
+    }
 }
 
 fn run_game(
+    options: &Options,
     audio: &mut Audio,
+    sounds: &SoundPack,
     render_tx: &mpsc::Sender<Frame>,
     last_size: &mut (u16, u16),
+    exporter: &mut Option<Exporter>,
+    pack: Option<Arc<Pack>>,
 ) -> Result<(), Box<dyn Error>> {
+    // New games start at the practice level, playing the level pack if there is one
+    let first_level = || {
+        let level = Level::starting_at(options.level);
+        match &pack {
+            Some(pack) => level.with_pack(pack.clone()),
+            None => level,
+        }
+    };
     let mut instant = Instant::now();
     let mut menu = Menu::new();
-    let mut difficulty = Difficulty::new(DifficultyLevel::Normal); // Default difficulty
-    let mut in_menu = true;
+    menu.daily_info = daily_info(&Challenge::today())?;
+    let mut daily_run: Option<Leaderboard> = None; // Board of the scored daily attempt in progress
+    let mut mode = Mode::Menu;
+    let mut idle = Duration::ZERO; // How long the menu has gone without a key press
 
     // Initialize game entities
     let mut curr_frame = new_frame(); // Initial frame
-    let mut player = Player::new(&difficulty); // Player with difficulty settings
-    let mut invaders = Invaders::new(&difficulty); // Invaders with difficulty settings
-    let mut score = Score::new();
-    let mut level = Level::new();
-
-    player.center(&curr_frame); // Center the player
-    invaders.populate(&curr_frame); // Populate invaders
+    let mut session = Session::solo(Game::new(
+        &Difficulty::default(),
+        Rng::from_time(),
+        &curr_frame,
+    ));
+    let mut keyboards = [Keyboard::new(), Keyboard::with_layout(Layout::Letters)];
+    let mut autopilot = Autopilot::default();
+    let mut heartbeat = Heartbeat::new();
 
     'gameloop: loop {
         // Adjust frame dimensions if terminal size changes
         let (new_term_width, new_term_height) = crossterm::terminal::size()?;
         if new_term_width != last_size.0 || new_term_height != last_size.1 {
             curr_frame = new_frame();
-            player.center(&curr_frame);
-            invaders.populate(&curr_frame);
+            session.resize(&curr_frame);
             *last_size = (new_term_width, new_term_height); // Update last known size
         }
 
@@ -83,115 +443,262 @@ fn run_game(
         instant = Instant::now();
         let mut curr_frame = new_frame();
 
-        if in_menu {
-            // Menu logic
-            while event::poll(Duration::default())? {
-                if let Event::Key(key_event) = event::read()? {
-                    match key_event.code {
-                        KeyCode::Up => menu.change_option(true),
-                        KeyCode::Down => menu.change_option(false),
-                        KeyCode::Left => menu.toggle_difficulty(true), // Toggle difficulty up
-                        KeyCode::Right => menu.toggle_difficulty(false), // Toggle difficulty down
-                        KeyCode::Char(' ') | KeyCode::Enter => {
-                            if menu.selection == 0 {
-                                difficulty =
-                                    Difficulty::new(match menu.get_selected_difficulty() {
-                                        "Easy" => DifficultyLevel::Easy,
-                                        "Normal" => DifficultyLevel::Normal,
-                                        "Hard" => DifficultyLevel::Hard,
-                                        "Hardcore" => DifficultyLevel::Hardcore,
-                                        _ => DifficultyLevel::Normal,
-                                    });
-                                player = Player::new(&difficulty);
-                                player.center(&curr_frame);
-                                invaders = Invaders::new(&difficulty);
-                                invaders.populate(&curr_frame);
-                                in_menu = false; // Exit menu and start the game
-                            } else {
-                                break 'gameloop; // Exit game
+        match mode {
+            Mode::Menu => {
+                idle += delta;
+                while event::poll(Duration::default())? {
+                    if let Event::Key(key_event) = event::read()? {
+                        idle = Duration::ZERO;
+                        match key_event.code {
+                            KeyCode::Up => menu.change_option(true),
+                            KeyCode::Down => menu.change_option(false),
+                            KeyCode::Left => menu.toggle_difficulty(true), // Toggle difficulty up
+                            KeyCode::Right => menu.toggle_difficulty(false), // Toggle difficulty down
+                            KeyCode::Char(' ') | KeyCode::Enter => {
+                                let difficulty = Difficulty::new(
+                                    DifficultyLevel::parse(menu.get_selected_difficulty())
+                                        .unwrap_or(DifficultyLevel::Normal),
+                                );
+                                let rng = options.seed.map_or_else(Rng::from_time, Rng::new);
+                                daily_run = None;
+                                session = match menu.options[menu.selection].as_str() {
+                                    "New game" => Session::solo(Game::starting_at(
+                                        first_level(),
+                                        1,
+                                        &difficulty,
+                                        rng,
+                                        &curr_frame,
+                                    )),
+                                    "Co-op" => Session::solo(Game::starting_at(
+                                        first_level(),
+                                        2,
+                                        &difficulty,
+                                        rng,
+                                        &curr_frame,
+                                    )),
+                                    "2 players" => Session::hot_seat(
+                                        first_level(),
+                                        2,
+                                        &difficulty,
+                                        rng.seed(),
+                                        &curr_frame,
+                                    ),
+                                    "Daily challenge" => {
+                                        // Everyone gets the same seed and difficulty for the day,
+                                        // but only the first attempt is scored
+                                        let challenge = Challenge::today();
+                                        let mut board =
+                                            Leaderboard::load(&daily::data_dir(), &challenge.date)?;
+                                        let name = daily::player_name();
+                                        if !board.attempted(&name) {
+                                            // Record the attempt up front so quitting can't retry it
+                                            board.record(Entry {
+                                                name,
+                                                score: 0,
+                                                level: 1,
+                                            })?;
+                                            daily_run = Some(board);
+                                        }
+                                        Session::solo(Game::new(
+                                            &challenge.difficulty(),
+                                            Rng::new(challenge.seed),
+                                            &curr_frame,
+                                        ))
+                                    }
+                                    _ => break 'gameloop, // Exit game
+                                };
+                                keyboards =
+                                    [Keyboard::new(), Keyboard::with_layout(Layout::Letters)];
+                                heartbeat.reset();
+                                mode = Mode::Playing; // Exit menu and start the game
                             }
+                            _ => {}
                         }
-                        _ => {}
                     }
                 }
-            }
-            menu.draw(&mut curr_frame);
-            let _ = render_tx.send(curr_frame);
-            thread::sleep(Duration::from_millis(1));
-            continue;
-        }
 
-        // Game input handling
-        while event::poll(Duration::default())? {
-            if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Left => player.move_left(&curr_frame),
-                    KeyCode::Right => player.move_right(&curr_frame),
-                    KeyCode::Char(' ') | KeyCode::Enter => {
-                        if player.shoot() {
-                            audio.play("pew");
-                        }
+                // Nobody is playing: let the autopilot show the game off
+                if mode == Mode::Menu && idle >= options.demo_after {
+                    idle = Duration::ZERO;
+                    session = Session::solo(Game::new(
+                        &Difficulty::default(),
+                        Rng::from_time(),
+                        &curr_frame,
+                    ));
+                    autopilot = Autopilot::default();
+                    mode = Mode::Demo;
+                }
+                if mode == Mode::Menu {
+                    menu.draw(&mut curr_frame);
+                    let _ = render_tx.send(curr_frame);
+                    thread::sleep(Duration::from_millis(1));
+                    continue;
+                }
+            }
+            Mode::Results(ref lines) => {
+                let top = (curr_frame[0].len() / 2).saturating_sub(lines.len() / 2);
+                for (index, line) in lines.iter().enumerate() {
+                    frame::draw_centered(&mut curr_frame, top + index, line);
+                }
+                while event::poll(Duration::default())? {
+                    if let Event::Key(_) = event::read()? {
+                        mode = Mode::Menu;
                     }
-                    KeyCode::Esc | KeyCode::Char('q') => {
-                        audio.play("lose");
-                        reset_game(
-                            &mut in_menu,
-                            &mut player,
-                            &mut invaders,
-                            &curr_frame,
-                            &difficulty,
-                        );
+                }
+                let _ = render_tx.send(curr_frame);
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+            Mode::Demo => {
+                // Any key press ends the demo
+                while event::poll(Duration::default())? {
+                    if let Event::Key(_) = event::read()? {
+                        mode = Mode::Menu;
                     }
-                    _ => {}
+                }
+                if mode == Mode::Menu {
+                    continue;
+                }
+            }
+            Mode::Playing => {
+                while event::poll(Duration::default())? {
+                    if let Event::Key(key_event) = event::read()? {
+                        match key_event.code {
+                            KeyCode::Esc | KeyCode::Char('q') => {
+                                audio.play("lose");
+                                mode = game_over(&mut menu, &session, &mut daily_run)?;
+                            }
+                            code => {
+                                // Each ship's keyboard picks out its own keys
+                                for keyboard in keyboards.iter_mut() {
+                                    keyboard.press(code);
+                                }
+                            }
+                        }
+                    }
+                }
+                if mode != Mode::Playing {
+                    continue;
                 }
             }
         }
 
         // Updates
-        player.update(delta);
-        if invaders.update(delta, &curr_frame) {
-            audio.play("move");
-        }
-        let hits = player.detect_hits(&mut invaders);
-        if hits > 0 {
-            audio.play("explode");
-            score.add_points(hits);
+        let actions: Vec<_> = if mode == Mode::Demo {
+            vec![autopilot.actions(session.game(), delta)]
+        } else {
+            keyboards
+                .iter_mut()
+                .map(|keyboard| keyboard.actions(session.game(), delta))
+                .collect()
+        };
+        let tick = session.step(delta, &actions, &curr_frame);
+
+        // The demo plays silently
+        if mode == Mode::Playing {
+            play_effects(audio, sounds, &mut heartbeat, &tick);
+            if let Some(exporter) = exporter.as_mut() {
+                exporter.publish(Snapshot::new(session.game(), tick.state))?;
+            }
         }
 
         // Draw and render
-        let drawables: Vec<&dyn Drawable> = vec![&player, &invaders, &score, &level];
-        for drawable in drawables {
-            drawable.draw(&mut curr_frame);
+        session.draw(&mut curr_frame);
+        if mode == Mode::Demo {
+            let bottom = curr_frame[0].len() - 1;
+            frame::draw_centered(&mut curr_frame, bottom, "DEMO - PRESS ANY KEY");
         }
         let _ = render_tx.send(curr_frame.clone());
         thread::sleep(Duration::from_millis(1));
 
         // Win or lose conditions
-        if invaders.all_killed() {
-            if level.increment_level() {
+        match (tick.state, &mode) {
+            (State::Playing, _) => {}
+            (_, Mode::Demo) => mode = Mode::Menu,
+            (State::Won, _) if session.players.len() > 1 => {
+                audio.play("win");
+                mode = game_over(&mut menu, &session, &mut daily_run)?;
+            }
+            (State::Won, _) => {
                 audio.play("win");
+                record_daily(&mut daily_run, session.game())?;
                 break 'gameloop;
             }
-            invaders.next_level(&curr_frame); // Reset invaders
-        } else if invaders.reached_bottom(&curr_frame) {
-            audio.play("lose");
-            reset_game(
-                &mut in_menu,
-                &mut player,
-                &mut invaders,
-                &curr_frame,
-                &difficulty,
-            );
+            (State::Lost, _) => {
+                audio.play("lose");
+                mode = game_over(&mut menu, &session, &mut daily_run)?;
+            }
         }
     }
     Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = Options::parse(std::env::args().skip(1))?;
+    if let Command::Simulate(config) = &options.command {
+        let summary = sim::Summary::new(&sim::run(config));
+        print!(
+            "{}",
+            if config.csv {
+                summary.csv()
+            } else {
+                summary.table()
+            }
+        );
+        return Ok(());
+    }
+    if let Command::Serve(port) = options.command {
+        return Ok(serve::serve(port, options.seed)?);
+    }
+
+    // Connect before the terminal is taken over, so errors show on the console
+    let watch = match &options.command {
+        Command::Watch(address) => Some(spectate::connect(address)?),
+        _ => None,
+    };
+    let mut exporter = match &options.export {
+        Some(target) => Some(Exporter::open(target)?),
+        None => None,
+    };
+    let broadcaster = match &options.broadcast {
+        Some(address) => Some(Broadcaster::bind(address)?),
+        None => None,
+    };
+    // A broken level file is reported on the console, before the terminal is taken over
+    let pack = match &options.pack {
+        Some(dir) => Some(Arc::new(Pack::load(Path::new(dir))?)),
+        None => None,
+    };
+    let editor = match &options.command {
+        Command::Edit(path) => Some(Editor::open(Path::new(path), &new_frame())?),
+        _ => None,
+    };
+
+    // Versus matches connect before the terminal is taken over, so progress shows on the console
+    let versus = match &options.command {
+        Command::Host(port) => {
+            let seed = options.seed.map_or_else(Rng::from_time, Rng::new).seed();
+            let listener = TcpListener::bind(("0.0.0.0", *port))?;
+            println!("Waiting for an opponent on port {}...", port);
+            Some((net::host(&listener, seed)?, seed))
+        }
+        Command::Join(address) => {
+            println!("Connecting to {}...", address);
+            Some(net::join(address.as_str())?)
+        }
+        _ => None,
+    };
+
     let mut audio = Audio::new();
-    for item in &["explode", "lose", "move", "pew", "startup", "win"] {
+    for item in &["lose", "startup", "win"] {
         audio.add(item, format!("sounds/{}.wav", item));
     }
+
+    // Synthesize the chiptune effects and hand them to the audio layer
+    let sounds = SoundPack::builtin();
+    for (name, path) in sounds.render(&std::env::temp_dir().join("invaders-sounds"))? {
+        audio.add(name, path);
+    }
     audio.play("startup");
 
     // Terminal setup
@@ -204,10 +711,26 @@ fn main() -> Result<(), Box<dyn Error>> {
     let (render_tx, render_rx) = mpsc::channel();
     let mut last_size = crossterm::terminal::size()?; // Track the initial terminal size
     let render_handle = thread::spawn(move || {
-        render_screen(render_rx, &mut last_size);
+        render_screen(render_rx, &mut last_size, broadcaster);
     });
 
-    let res = run_game(&mut audio, &render_tx, &mut last_size);
+    let res = match (watch, versus, editor) {
+        (Some(stream), _, _) => run_watch(stream, &render_tx),
+        (_, _, Some(editor)) => run_edit(editor, &mut audio, &sounds, &render_tx, &mut last_size),
+        (_, Some((link, seed)), _) => {
+            let versus = Versus::new(link, &Difficulty::default(), seed, &new_frame());
+            run_versus(versus, &mut audio, &sounds, &render_tx, &mut exporter)
+        }
+        _ => run_game(
+            &options,
+            &mut audio,
+            &sounds,
+            &render_tx,
+            &mut last_size,
+            &mut exporter,
+            pack,
+        ),
+    };
 
     // Cleanup
     drop(render_tx);
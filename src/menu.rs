@@ -1,15 +1,23 @@
-use crate::frame::{Drawable, Frame};
+use crate::frame::{draw_text, Drawable, Frame};
 pub struct Menu {
     pub options: Vec<String>,
     pub selection: usize,
     pub difficulty_levels: Vec<String>,
     pub current_difficulty: usize,
+    pub status: Option<String>, // Message from the last game, e.g. the game-over summary
+    pub daily_info: Vec<String>, // Today's challenge and leaderboard, shown while it is selected
 }
 
 impl Menu {
     pub fn new() -> Self {
         Self {
-            options: vec![String::from("New game"), String::from("Exit")],
+            options: vec![
+                String::from("New game"),
+                String::from("Co-op"),
+                String::from("2 players"),
+                String::from("Daily challenge"),
+                String::from("Exit"),
+            ],
             selection: 0,
             difficulty_levels: vec![
                 String::from("Easy"),
@@ -18,6 +26,8 @@ impl Menu {
                 String::from("Hardcore"),
             ],
             current_difficulty: 1, // Default to "Normal"
+            status: None,
+            daily_info: Vec::new(),
         }
     }
 
@@ -56,7 +66,7 @@ impl Drawable for Menu {
         let title_start_y = menu_start_y - 7; // Multiline title starts higher
 
         // Render multiline title
-        let title = vec![
+        let title = [
            "  ______                               __                          ",
            " /\\__  _\\                             /\\ \\                         ",
            " \\/_/\\ \\/     ___   __  __     __     \\_\\ \\     __   _ __   ____   ",
@@ -93,5 +103,18 @@ impl Drawable for Menu {
                 frame[i + 1][menu_start_y + index * 2] = c;
             }
         }
+
+        // Render the last game's summary below the options
+        let status_y = menu_start_y + self.options.len() * 2;
+        if let Some(status) = &self.status {
+            draw_text(frame, 1, status_y, status);
+        }
+
+        // Render the daily challenge details while it is highlighted
+        if self.options[self.selection] == "Daily challenge" {
+            for (line_index, line) in self.daily_info.iter().enumerate() {
+                draw_text(frame, 1, status_y + 2 + line_index, line);
+            }
+        }
     }
 }
@@ -0,0 +1,79 @@
+use crate::events::GameEvent;
+
+// The four-note bass loop, synthesized from the sound pack
+const NOTES: [&str; 4] = ["march0", "march1", "march2", "march3"];
+
+// One note of the loop for every step the army takes, so it speeds up with the march
+#[derive(Default)]
+pub struct Heartbeat {
+    note: usize,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The note to play for `event`, if it's the army taking a step
+    pub fn beat(&mut self, event: &GameEvent) -> Option<&'static str> {
+        match event {
+            GameEvent::ArmyStepped | GameEvent::ArmyDescended => {
+                let note = NOTES[self.note];
+                self.note = (self.note + 1) % NOTES.len();
+                Some(note)
+            }
+            _ => None,
+        }
+    }
+
+    // Restart the loop from its first note
+    pub fn reset(&mut self) {
+        self.note = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{difficulty::Difficulty, frame::sized_frame, game::Game, rng::Rng};
+    use std::time::Duration;
+
+    #[test]
+    fn should_cycle_through_four_notes() {
+        // given
+        let mut heartbeat = Heartbeat::new();
+        // when
+        let notes: Vec<_> = (0..5)
+            .filter_map(|_| heartbeat.beat(&GameEvent::ArmyStepped))
+            .collect();
+        // then
+        assert_eq!(
+            vec!["march0", "march1", "march2", "march3", "march0"],
+            notes
+        );
+    }
+
+    #[test]
+    fn should_play_a_note_on_every_army_step() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        let mut heartbeat = Heartbeat::new();
+        let mut steps = 0;
+        let mut notes = 0;
+        // when
+        for _ in 0..200 {
+            let tick = game.step(Duration::from_millis(50), &[], &frame);
+            for event in &tick.events {
+                let marched = matches!(event, GameEvent::ArmyStepped | GameEvent::ArmyDescended);
+                let note = heartbeat.beat(event);
+                assert_eq!(marched, note.is_some());
+                steps += usize::from(marched);
+                notes += usize::from(note.is_some());
+            }
+        }
+        // then
+        assert!(steps > 0);
+        assert_eq!(steps, notes);
+    }
+}
@@ -0,0 +1,515 @@
+// Networked versus mode: two players race identical seeded waves over TCP, and
+// wiping out a row of your army drops garbage invaders onto the opponent's field.
+//
+// The protocol is one text message per line, starting with a version handshake
+// in which the host hands its seed to the joiner. After that each side sends
+// its status a few times a second, which doubles as the keep-alive.
+use crate::{
+    controller::Action,
+    difficulty::Difficulty,
+    frame::{draw_text, Drawable, Frame},
+    game::{Game, State, Tick},
+    rng::Rng,
+};
+use rusty_time::Timer;
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    str::{FromStr, SplitWhitespace},
+    time::Duration,
+};
+
+// Bumped whenever the message format changes; both ends must match
+pub const PROTOCOL_VERSION: u32 = 2;
+// Garbage invaders sent for every row cleared
+pub const GARBAGE_PER_ROW: u16 = 2;
+// How often our status goes out
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+// The opponent counts as gone after this long without a message
+const TIMEOUT: Duration = Duration::from_secs(5);
+// How long to wait for the other side's hello
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    Hello { version: u32, seed: u64 },
+    Status { score: u32, level: u16, lives: u8 },
+    Garbage(u16),
+    Over,    // Sender ran out of lives
+    Cleared, // Sender beat every level
+    Bye,     // Sender quit
+}
+
+impl Message {
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Hello { version, seed } => format!("HELLO {} {}\n", version, seed),
+            Message::Status {
+                score,
+                level,
+                lives,
+            } => format!("STATUS {} {} {}\n", score, level, lives),
+            Message::Garbage(count) => format!("GARBAGE {}\n", count),
+            Message::Over => String::from("OVER\n"),
+            Message::Cleared => String::from("CLEARED\n"),
+            Message::Bye => String::from("BYE\n"),
+        }
+    }
+
+    // Parse one line, or None for anything unrecognised so newer peers can add messages
+    pub fn decode(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let message = match words.next()? {
+            "HELLO" => Message::Hello {
+                version: field(&mut words)?,
+                seed: field(&mut words)?,
+            },
+            "STATUS" => Message::Status {
+                score: field(&mut words)?,
+                level: field(&mut words)?,
+                lives: field(&mut words)?,
+            },
+            "GARBAGE" => Message::Garbage(field(&mut words)?),
+            "OVER" => Message::Over,
+            "CLEARED" => Message::Cleared,
+            "BYE" => Message::Bye,
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+fn field<T: FromStr>(words: &mut SplitWhitespace) -> Option<T> {
+    words.next()?.parse().ok()
+}
+
+// A connection to the opponent. Reads and writes never block once the handshake
+// is done, so a slow link only delays updates either way, never our own game.
+pub struct Link {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    outgoing: Vec<u8>, // Sent messages the socket hasn't taken yet
+    silence: Duration, // Time since we last heard from the opponent
+    connected: bool,
+}
+
+impl Link {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            outgoing: Vec::new(),
+            silence: Duration::ZERO,
+            connected: true,
+        })
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn send(&mut self, message: &Message) {
+        self.outgoing.extend_from_slice(message.encode().as_bytes());
+        self.flush();
+    }
+
+    // Hand the socket as much of the outgoing buffer as it takes without blocking
+    fn flush(&mut self) {
+        while self.connected && !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => self.connected = false,
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.connected = false,
+            }
+        }
+    }
+
+    // Messages that arrived since the last poll, `delta` ago
+    pub fn poll(&mut self, delta: Duration) -> Vec<Message> {
+        self.flush();
+        let mut chunk = [0; 512];
+        while self.connected {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => self.connected = false,
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.connected = false,
+            }
+        }
+
+        // Decode every complete line, then drop them from the buffer in one go
+        let mut messages = Vec::new();
+        let mut start = 0;
+        while let Some(end) = self.buffer[start..].iter().position(|&byte| byte == b'\n') {
+            let line = &self.buffer[start..=start + end];
+            messages.extend(Message::decode(&String::from_utf8_lossy(line)));
+            start += end + 1;
+        }
+        self.buffer.drain(..start);
+        if messages.is_empty() {
+            self.silence += delta;
+            if self.silence >= TIMEOUT {
+                self.connected = false;
+            }
+        } else {
+            self.silence = Duration::ZERO;
+        }
+        messages
+    }
+
+    // Pop the next complete line off the buffer, decoded if we understand it
+    fn next_line(&mut self) -> Option<Option<Message>> {
+        let end = self.buffer.iter().position(|&byte| byte == b'\n')?;
+        let line: Vec<u8> = self.buffer.drain(..=end).collect();
+        Some(Message::decode(&String::from_utf8_lossy(&line)))
+    }
+
+    // Block until the next message, for the handshake
+    fn receive(&mut self) -> io::Result<Message> {
+        let mut chunk = [0; 512];
+        loop {
+            if let Some(Some(message)) = self.next_line() {
+                return Ok(message);
+            }
+            match self.stream.read(&mut chunk)? {
+                0 => return Err(ErrorKind::UnexpectedEof.into()),
+                read => self.buffer.extend_from_slice(&chunk[..read]),
+            }
+        }
+    }
+
+    // Check the other side's hello, then switch to non-blocking play
+    fn handshake(&mut self) -> io::Result<u64> {
+        match self.receive()? {
+            Message::Hello { version, seed } if version == PROTOCOL_VERSION => {
+                self.stream.set_nonblocking(true)?;
+                Ok(seed)
+            }
+            Message::Hello { version, .. } => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "opponent speaks protocol v{}, this game speaks v{}",
+                    version, PROTOCOL_VERSION
+                ),
+            )),
+            other => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("expected a hello, got {:?}", other),
+            )),
+        }
+    }
+}
+
+// Wait for an opponent to connect, and hand them the seed for both games
+pub fn host(listener: &TcpListener, seed: u64) -> io::Result<Link> {
+    let (stream, _) = listener.accept()?;
+    let mut link = Link::new(stream)?;
+    link.send(&Message::Hello {
+        version: PROTOCOL_VERSION,
+        seed,
+    });
+    link.handshake()?;
+    Ok(link)
+}
+
+// Connect to a host, returning the link and the seed it picked
+pub fn join<A: ToSocketAddrs>(address: A) -> io::Result<(Link, u64)> {
+    let mut link = Link::new(TcpStream::connect(address)?)?;
+    let seed = link.handshake()?;
+    link.send(&Message::Hello {
+        version: PROTOCOL_VERSION,
+        seed,
+    });
+    Ok((link, seed))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Won,
+    Lost,
+    Disconnected, // The opponent quit or dropped; counts as a win
+}
+
+// The opponent as last reported
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Opponent {
+    pub score: u32,
+    pub level: u16,
+    pub lives: u8,
+}
+
+// Our side of a versus match
+pub struct Versus {
+    pub game: Game,
+    pub opponent: Option<Opponent>, // None until the first status arrives
+    pub outcome: Option<Outcome>,
+    link: Link,
+    status_timer: Timer,
+}
+
+impl Versus {
+    pub fn new(link: Link, difficulty: &Difficulty, seed: u64, frame: &Frame) -> Self {
+        Self {
+            game: Game::new(difficulty, Rng::new(seed), frame),
+            opponent: None,
+            outcome: None,
+            link,
+            status_timer: Timer::new(STATUS_INTERVAL),
+        }
+    }
+
+    // Step our game and trade garbage and status with the opponent
+    pub fn step(&mut self, delta: Duration, actions: &[Vec<Action>], frame: &Frame) -> Tick {
+        if self.outcome.is_some() {
+            return Tick {
+                state: self.state(),
+                ..Tick::default()
+            };
+        }
+
+        let mut tick = self.game.step(delta, actions, frame);
+        if tick.rows_cleared > 0 && tick.state == State::Playing {
+            self.link
+                .send(&Message::Garbage(tick.rows_cleared * GARBAGE_PER_ROW));
+        }
+
+        for message in self.link.poll(delta) {
+            match message {
+                Message::Status {
+                    score,
+                    level,
+                    lives,
+                } => {
+                    self.opponent = Some(Opponent {
+                        score,
+                        level,
+                        lives,
+                    })
+                }
+                Message::Garbage(count) => {
+                    self.game.invaders.add_garbage(count as usize, frame);
+                }
+                Message::Over => self.outcome = Some(Outcome::Won),
+                Message::Cleared => self.outcome = Some(Outcome::Lost),
+                Message::Bye => self.outcome = Some(Outcome::Disconnected),
+                Message::Hello { .. } => {}
+            }
+        }
+
+        self.status_timer.tick(delta);
+        if self.status_timer.finished() {
+            self.status_timer.reset();
+            self.send_status();
+        }
+
+        match tick.state {
+            State::Lost => {
+                self.link.send(&Message::Over);
+                self.outcome = Some(Outcome::Lost);
+            }
+            State::Won => {
+                // Beat every level before the opponent ran out of lives
+                self.link.send(&Message::Cleared);
+                self.outcome = Some(Outcome::Won);
+            }
+            State::Playing => {}
+        }
+        if !self.link.connected() && self.outcome.is_none() {
+            self.outcome = Some(Outcome::Disconnected);
+        }
+        tick.state = self.state();
+        tick
+    }
+
+    // Tell the opponent we're leaving, so they aren't left waiting for the timeout
+    pub fn quit(&mut self) {
+        self.link.send(&Message::Bye);
+    }
+
+    fn send_status(&mut self) {
+        self.link.send(&Message::Status {
+            score: self.game.score.points(),
+            level: self.game.level.level(),
+            lives: self.game.lives,
+        });
+    }
+
+    fn state(&self) -> State {
+        match self.outcome {
+            None => State::Playing,
+            Some(Outcome::Lost) => State::Lost,
+            Some(Outcome::Won | Outcome::Disconnected) => State::Won,
+        }
+    }
+}
+
+impl Drawable for Versus {
+    fn draw(&self, frame: &mut Frame) {
+        self.game.draw(frame);
+        let text = match (self.opponent, self.outcome) {
+            (_, Some(Outcome::Disconnected)) => String::from("VS: DISCONNECTED"),
+            (None, _) => String::from("VS: WAITING"),
+            (Some(opponent), _) => format!(
+                "VS SCORE: {:0>4}  LEVEL: {:0>2}  LIVES: {}",
+                opponent.score, opponent.level, opponent.lives
+            ),
+        };
+        draw_text(frame, 0, 1, &text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        frame::sized_frame,
+        invaders::Invaders,
+        level::{Level, MAX_LEVEL},
+    };
+    use std::thread;
+
+    // Host and joiner connected over loopback
+    fn connect(seed: u64) -> (Link, Link) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let joiner = thread::spawn(move || join(address).unwrap());
+        let host = host(&listener, seed).unwrap();
+        let (joined, joined_seed) = joiner.join().unwrap();
+        assert_eq!(seed, joined_seed);
+        (host, joined)
+    }
+
+    // Step until `done` holds, giving the loopback time to deliver
+    fn step_until(versus: &mut Versus, frame: &Frame, done: impl Fn(&Versus) -> bool) {
+        for _ in 0..200 {
+            versus.step(Duration::from_millis(1), &[], frame);
+            if done(versus) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("gave up waiting on the loopback link");
+    }
+
+    #[test]
+    fn should_round_trip_messages() {
+        // given
+        let messages = [
+            Message::Hello {
+                version: PROTOCOL_VERSION,
+                seed: 42,
+            },
+            Message::Status {
+                score: 120,
+                level: 3,
+                lives: 2,
+            },
+            Message::Garbage(4),
+            Message::Over,
+            Message::Cleared,
+            Message::Bye,
+        ];
+        // then
+        for message in messages {
+            assert_eq!(Some(message), Message::decode(&message.encode()));
+        }
+        assert_eq!(None, Message::decode("GARBAGE lots\n"));
+        assert_eq!(None, Message::decode("TAUNT\n"));
+    }
+
+    #[test]
+    fn should_reject_other_protocol_versions() {
+        // given
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let joiner = thread::spawn(move || join(address).map(|_| ()));
+        let (mut stream, _) = listener.accept().unwrap();
+        // when
+        stream.write_all(b"HELLO 99 1\n").unwrap();
+        // then
+        let error = joiner.join().unwrap().unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn should_drop_garbage_on_the_opponent() {
+        // given
+        let frame = sized_frame(40, 20);
+        let (mut host, joined) = connect(7);
+        let mut versus = Versus::new(joined, &Difficulty::default(), 7, &frame);
+        let before = versus.game.invaders.remaining();
+        // when
+        host.send(&Message::Garbage(3));
+        step_until(&mut versus, &frame, |versus| {
+            versus.game.invaders.remaining() > before
+        });
+        // then
+        assert_eq!(before + 3, versus.game.invaders.remaining());
+        assert_eq!(None, versus.outcome);
+    }
+
+    #[test]
+    fn should_lose_when_opponent_clears_every_level() {
+        // given
+        let frame = sized_frame(40, 20);
+        let (host, joined) = connect(7);
+        let mut winner = Versus::new(host, &Difficulty::default(), 7, &frame);
+        let mut loser = Versus::new(joined, &Difficulty::default(), 7, &frame);
+        winner.game.level = Level::starting_at(MAX_LEVEL);
+        winner.game.invaders = Invaders::default();
+        winner.game.boss = None;
+        // when
+        let tick = winner.step(Duration::from_millis(1), &[], &frame);
+        step_until(&mut loser, &frame, |versus| versus.outcome.is_some());
+        // then
+        assert_eq!(State::Won, tick.state);
+        assert_eq!(Some(Outcome::Lost), loser.outcome);
+    }
+
+    #[test]
+    fn should_win_when_opponent_disconnects() {
+        // given
+        let frame = sized_frame(40, 20);
+        let (host, joined) = connect(7);
+        let mut versus = Versus::new(joined, &Difficulty::default(), 7, &frame);
+        // when
+        drop(host);
+        step_until(&mut versus, &frame, |versus| versus.outcome.is_some());
+        // then
+        assert_eq!(Some(Outcome::Disconnected), versus.outcome);
+    }
+
+    #[test]
+    fn should_hold_messages_back_while_the_link_is_congested() {
+        // given
+        let (mut host, mut joined) = connect(7);
+        let status = Message::Status {
+            score: 1,
+            level: 1,
+            lives: 3,
+        };
+        // when
+        let mut sent = 0;
+        while joined.outgoing.is_empty() {
+            joined.send(&status);
+            sent += 1;
+        }
+        let congested = joined.connected();
+        let mut received = 0;
+        while received < sent {
+            joined.poll(Duration::ZERO);
+            received += host.poll(Duration::ZERO).len();
+        }
+        // then
+        assert!(congested);
+        assert!(joined.outgoing.is_empty());
+        assert_eq!(sent, received);
+    }
+}
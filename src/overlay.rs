@@ -0,0 +1,168 @@
+// Live game state for stream overlays and dashboards, as JSON lines. A snapshot is
+// published whenever the score, level, lives, army or game state changes, either by
+// atomically rewriting a file or to every reader connected to a socket.
+use crate::{
+    game::{Game, State},
+    spectate::Listener,
+};
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    File(String),   // Rewritten with the latest snapshot
+    Socket(String), // Unix socket path or TCP address streaming every snapshot
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot {
+    pub score: u32,
+    pub level: u16,
+    pub lives: u8,
+    pub invaders: usize, // Invaders left in the wave
+    pub shots: u32,
+    pub kills: u32,
+    pub state: State,
+}
+
+impl Snapshot {
+    pub fn new(game: &Game, state: State) -> Self {
+        Self {
+            score: game.score.points(),
+            level: game.level.level(),
+            lives: game.lives,
+            invaders: game.invaders.remaining(),
+            shots: game.tally.shots,
+            kills: game.tally.kills,
+            state,
+        }
+    }
+
+    // Kills per shot fired
+    pub fn accuracy(&self) -> f64 {
+        if self.shots == 0 {
+            0.0
+        } else {
+            self.kills as f64 / self.shots as f64
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let state = match self.state {
+            State::Playing => "playing",
+            State::Lost => "lost",
+            State::Won => "won",
+        };
+        format!(
+            r#"{{"score":{},"level":{},"lives":{},"invaders":{},"shots":{},"kills":{},"accuracy":{:.3},"state":"{}"}}"#,
+            self.score,
+            self.level,
+            self.lives,
+            self.invaders,
+            self.shots,
+            self.kills,
+            self.accuracy(),
+            state
+        )
+    }
+}
+
+enum Sink {
+    File(PathBuf),
+    Socket(Listener, Vec<Box<dyn Write + Send>>),
+}
+
+pub struct Exporter {
+    sink: Sink,
+    last: Option<Snapshot>,
+}
+
+impl Exporter {
+    pub fn open(target: &Target) -> io::Result<Self> {
+        let sink = match target {
+            Target::File(path) => Sink::File(PathBuf::from(path)),
+            Target::Socket(address) => Sink::Socket(Listener::bind(address)?, Vec::new()),
+        };
+        Ok(Self { sink, last: None })
+    }
+
+    // Publish the snapshot if anything changed; readers who just connected always get it
+    pub fn publish(&mut self, snapshot: Snapshot) -> io::Result<()> {
+        let changed = self.last != Some(snapshot);
+        let line = snapshot.to_json() + "\n";
+        match &mut self.sink {
+            Sink::File(path) if changed => {
+                // Write beside the file and rename over it, so readers never see half a line
+                let mut temp = path.clone().into_os_string();
+                temp.push(".tmp");
+                fs::write(&temp, &line)?;
+                fs::rename(&temp, path)?;
+            }
+            Sink::File(_) => {}
+            Sink::Socket(listener, readers) => {
+                if changed {
+                    readers.retain_mut(|reader| reader.write_all(line.as_bytes()).is_ok());
+                }
+                for mut reader in listener.accept() {
+                    if reader.write_all(line.as_bytes()).is_ok() {
+                        readers.push(reader);
+                    }
+                }
+            }
+        }
+        self.last = Some(snapshot);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{difficulty::Difficulty, frame::sized_frame, rng::Rng};
+
+    #[test]
+    fn should_describe_game_as_json() {
+        // given
+        let frame = sized_frame(40, 20);
+        let mut game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        game.score.add_points(3);
+        (game.tally.shots, game.tally.kills) = (4, 3);
+        // when
+        let json = Snapshot::new(&game, State::Playing).to_json();
+        // then
+        assert_eq!(
+            r#"{"score":3,"level":1,"lives":3,"invaders":3,"shots":4,"kills":3,"accuracy":0.750,"state":"playing"}"#,
+            json
+        );
+    }
+
+    #[test]
+    fn should_rewrite_file_only_on_change() {
+        // given
+        let path = std::env::temp_dir().join(format!("invaders-state-{}.json", std::process::id()));
+        let mut exporter = Exporter::open(&Target::File(path.to_str().unwrap().into())).unwrap();
+        let frame = sized_frame(40, 20);
+        let mut game = Game::new(&Difficulty::default(), Rng::new(1), &frame);
+        exporter
+            .publish(Snapshot::new(&game, State::Playing))
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        // when
+        exporter
+            .publish(Snapshot::new(&game, State::Playing))
+            .unwrap();
+        let unchanged = path.exists();
+        game.lives -= 1;
+        exporter
+            .publish(Snapshot::new(&game, State::Playing))
+            .unwrap();
+        // then
+        assert!(!unchanged);
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains(r#""lives":2"#));
+        fs::remove_file(&path).unwrap();
+    }
+}
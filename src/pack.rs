@@ -0,0 +1,472 @@
+// Level packs: a directory of hand-written levels, `level01.txt`, `level02.txt`...
+// Levels a pack doesn't cover fall back to the procedural waves.
+//
+//   # Comment lines start with `#`, outside of maps
+//   name The Wall
+//   march 500              ms between army steps with a full army
+//   curve 0.15 1.5         speed-up as the army thins out: fastest fraction, exponent
+//   bombs 1500             ms between invader bombs, or `off`
+//   pattern dive           how the army moves: classic, sway, split, dive or kamikaze
+//   at 20 garbage 6        20s in, drop 6 extra invaders on the top row
+//   at 30 powerup shield   30s in, drop a power-up capsule
+//   formation              invaders: `x` soldier, `X` elite, `H` armoured, `.` or space for none
+//   x x x x x
+//   XXXXXXXXX
+//   end
+//   bunkers                blocks above the ships: `#`, `.` or space for none
+//   ##   ##
+//   end
+//
+// Further `formation` maps arrive as reinforcements, one after another.
+use crate::{
+    difficulty::SpeedCurve, invaders::Kind, level::MAX_LEVEL, pattern::Pattern, powerup::PowerUp,
+};
+use std::{collections::BTreeMap, error::Error, fmt, fs, path::Path, sync::Arc, time::Duration};
+
+// An invader's place in a formation map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spot {
+    pub x: usize,
+    pub y: usize,
+    pub kind: Kind,
+}
+
+// Something a level makes happen at a set time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Special {
+    Garbage(usize),
+    PowerUp(PowerUp),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cue {
+    pub at: Duration, // Since the level started
+    pub special: Special,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelFile {
+    pub name: String,
+    pub march: Option<Duration>,
+    pub curve: Option<SpeedCurve>,
+    pub bombs: Option<Option<Duration>>, // None keeps the difficulty's bomb rate
+    pub pattern: Option<Pattern>,        // None marches classically
+    pub cues: Vec<Cue>,
+    pub formations: Vec<Vec<Spot>>,
+    pub bunkers: Vec<(usize, usize)>,
+}
+
+// What was wrong with a level file, and where
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+impl LevelFile {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut level = LevelFile::default();
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line));
+        while let Some((number, line)) = lines.next() {
+            let error = |message: String| ParseError {
+                line: number,
+                message,
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => {}
+                [first, ..] if first.starts_with('#') => {}
+                ["name", ..] => level.name = line.trim()["name".len()..].trim().to_string(),
+                ["march", ms] => level.march = Some(Duration::from_millis(number_of(ms, error)?)),
+                ["curve", fastest, exponent] => {
+                    let (fastest, exponent): (f32, f32) =
+                        (number_of(fastest, error)?, number_of(exponent, error)?);
+                    if !(0.0..=1.0).contains(&fastest) {
+                        return Err(error(format!(
+                            "fastest `{}` isn't between 0 and 1",
+                            fastest
+                        )));
+                    }
+                    if !(exponent > 0.0 && exponent.is_finite()) {
+                        return Err(error(format!("exponent `{}` isn't above 0", exponent)));
+                    }
+                    level.curve = Some(SpeedCurve { fastest, exponent })
+                }
+                ["bombs", "off"] => level.bombs = Some(None),
+                ["bombs", ms] => {
+                    level.bombs = Some(Some(Duration::from_millis(number_of(ms, error)?)))
+                }
+                ["pattern", name] => {
+                    level.pattern = Some(
+                        Pattern::parse(name)
+                            .ok_or_else(|| error(format!("unknown pattern `{}`", name)))?,
+                    )
+                }
+                ["at", seconds, "garbage", count] => level.cues.push(Cue {
+                    at: seconds_of(seconds, error)?,
+                    special: Special::Garbage(number_of(count, error)?),
+                }),
+                ["at", seconds, "powerup", name] => level.cues.push(Cue {
+                    at: seconds_of(seconds, error)?,
+                    special: Special::PowerUp(
+                        PowerUp::ALL
+                            .into_iter()
+                            .find(|kind| kind.name() == name)
+                            .ok_or_else(|| error(format!("unknown power-up `{}`", name)))?,
+                    ),
+                }),
+                ["formation"] => {
+                    let mut spots = Vec::new();
+                    for (y, row) in read_map(number, &mut lines)?.iter().enumerate() {
+                        for (x, glyph) in row.chars().enumerate() {
+                            let kind = match glyph {
+                                'x' => Kind::Soldier,
+                                'X' => Kind::Elite,
+                                'H' => Kind::Armoured,
+                                '.' | ' ' => continue,
+                                _ => {
+                                    return Err(ParseError {
+                                        line: number + 1 + y,
+                                        message: format!("unknown invader `{}`", glyph),
+                                    })
+                                }
+                            };
+                            spots.push(Spot { x, y, kind });
+                        }
+                    }
+                    if spots.is_empty() {
+                        return Err(error("formation has no invaders".into()));
+                    }
+                    level.formations.push(spots);
+                }
+                ["bunkers"] => {
+                    if !level.bunkers.is_empty() {
+                        return Err(error("only one bunkers map is allowed".into()));
+                    }
+                    for (y, row) in read_map(number, &mut lines)?.iter().enumerate() {
+                        for (x, glyph) in row.chars().enumerate() {
+                            match glyph {
+                                '#' => level.bunkers.push((x, y)),
+                                '.' | ' ' => {}
+                                _ => {
+                                    return Err(ParseError {
+                                        line: number + 1 + y,
+                                        message: format!("unknown bunker block `{}`", glyph),
+                                    })
+                                }
+                            }
+                        }
+                    }
+                }
+                [key, ..] => return Err(error(format!("unknown setting `{}`", key))),
+            }
+        }
+        if level.formations.is_empty() {
+            return Err(ParseError {
+                line: text.lines().count().max(1),
+                message: "level needs at least one formation".into(),
+            });
+        }
+        level.cues.sort_by_key(|cue| cue.at);
+        Ok(level)
+    }
+
+    // Write the level back out in the same format, for the editor
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        if !self.name.is_empty() {
+            out += &format!("name {}\n", self.name);
+        }
+        if let Some(march) = self.march {
+            out += &format!("march {}\n", march.as_millis());
+        }
+        if let Some(curve) = self.curve {
+            out += &format!("curve {} {}\n", curve.fastest, curve.exponent);
+        }
+        match self.bombs {
+            Some(Some(rate)) => out += &format!("bombs {}\n", rate.as_millis()),
+            Some(None) => out += "bombs off\n",
+            None => {}
+        }
+        if let Some(pattern) = self.pattern {
+            out += &format!("pattern {}\n", pattern.name());
+        }
+        for cue in self.cues.iter() {
+            out += &match cue.special {
+                Special::Garbage(count) => {
+                    format!("at {} garbage {}\n", cue.at.as_secs_f32(), count)
+                }
+                Special::PowerUp(kind) => {
+                    format!("at {} powerup {}\n", cue.at.as_secs_f32(), kind.name())
+                }
+            };
+        }
+        for spots in self.formations.iter() {
+            let cells = spots.iter().map(|spot| {
+                let glyph = match spot.kind {
+                    Kind::Elite => 'X',
+                    Kind::Armoured => 'H',
+                    _ => 'x',
+                };
+                (spot.x, spot.y, glyph)
+            });
+            out += &format!("formation\n{}end\n", draw_map(cells));
+        }
+        if !self.bunkers.is_empty() {
+            let cells = self.bunkers.iter().map(|&(x, y)| (x, y, '#'));
+            out += &format!("bunkers\n{}end\n", draw_map(cells));
+        }
+        out
+    }
+
+    // Width and height of the formations, for centering them on screen
+    pub fn formation_size(&self) -> (usize, usize) {
+        size(
+            self.formations
+                .iter()
+                .flatten()
+                .map(|spot| (spot.x, spot.y)),
+        )
+    }
+
+    pub fn bunkers_size(&self) -> (usize, usize) {
+        size(self.bunkers.iter().copied())
+    }
+}
+
+// The lines of a map, up to its `end`
+fn read_map<'a, I: Iterator<Item = (usize, &'a str)>>(
+    start: usize,
+    lines: &mut I,
+) -> Result<Vec<&'a str>, ParseError> {
+    let mut rows = Vec::new();
+    for (_, line) in lines.by_ref() {
+        if line.trim() == "end" {
+            return Ok(rows);
+        }
+        rows.push(line.trim_end());
+    }
+    Err(ParseError {
+        line: start,
+        message: "map has no `end`".into(),
+    })
+}
+
+fn draw_map<I: Iterator<Item = (usize, usize, char)>>(cells: I) -> String {
+    let mut rows: Vec<Vec<char>> = Vec::new();
+    for (x, y, glyph) in cells {
+        if rows.len() <= y {
+            rows.resize(y + 1, Vec::new());
+        }
+        if rows[y].len() <= x {
+            rows[y].resize(x + 1, '.');
+        }
+        rows[y][x] = glyph;
+    }
+    rows.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+fn size<I: Iterator<Item = (usize, usize)>>(cells: I) -> (usize, usize) {
+    cells.fold((0, 0), |(width, height), (x, y)| {
+        (width.max(x + 1), height.max(y + 1))
+    })
+}
+
+fn number_of<T: std::str::FromStr>(
+    word: &str,
+    error: impl Fn(String) -> ParseError,
+) -> Result<T, ParseError> {
+    word.parse()
+        .map_err(|_| error(format!("`{}` isn't a valid number", word)))
+}
+
+// A time since the level started, in seconds; negative or endless times are mistakes
+fn seconds_of(word: &str, error: impl Fn(String) -> ParseError) -> Result<Duration, ParseError> {
+    let seconds: f32 = number_of(word, &error)?;
+    Duration::try_from_secs_f32(seconds)
+        .map_err(|_| error(format!("`{}` isn't a valid time", word)))
+}
+
+// The levels of a pack, by level number
+#[derive(Debug, Default)]
+pub struct Pack {
+    levels: BTreeMap<u16, Arc<LevelFile>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PackError(pub String);
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for PackError {}
+
+impl Pack {
+    // Load every `level<N>.txt` in a directory, reporting the first problem by file and line
+    pub fn load(dir: &Path) -> Result<Self, PackError> {
+        let entries = fs::read_dir(dir)
+            .map_err(|error| PackError(format!("{}: {}", dir.display(), error)))?;
+        // Sorted, so the problem reported is the same on every machine
+        let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        let mut pack = Pack::default();
+        for path in paths {
+            let Some(number) = level_number(&path) else {
+                continue;
+            };
+            let text = fs::read_to_string(&path)
+                .map_err(|error| PackError(format!("{}: {}", path.display(), error)))?;
+            let level = LevelFile::parse(&text)
+                .map_err(|error| PackError(format!("{}:{}", path.display(), error)))?;
+            if pack.levels.insert(number, Arc::new(level)).is_some() {
+                return Err(PackError(format!(
+                    "{}: level {} is defined twice",
+                    path.display(),
+                    number
+                )));
+            }
+        }
+        if pack.levels.is_empty() {
+            return Err(PackError(format!(
+                "{}: no level files (level01.txt, level02.txt...)",
+                dir.display()
+            )));
+        }
+        Ok(pack)
+    }
+
+    pub fn get(&self, level: u16) -> Option<&Arc<LevelFile>> {
+        self.levels.get(&level)
+    }
+
+    pub fn insert(&mut self, level: u16, file: LevelFile) {
+        self.levels.insert(level, Arc::new(file));
+    }
+}
+
+// Level number from a file name like `level07.txt`
+pub fn level_number(path: &Path) -> Option<u16> {
+    if path.extension()? != "txt" {
+        return None;
+    }
+    let number: u16 = path
+        .file_stem()?
+        .to_str()?
+        .strip_prefix("level")?
+        .parse()
+        .ok()?;
+    (1..=MAX_LEVEL).contains(&number).then_some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALL: &str = "\
+# The Wall
+name The Wall
+march 500
+bombs off
+pattern split
+at 20 garbage 6
+formation
+x.x
+XXX
+end
+bunkers
+#.#
+end
+";
+
+    #[test]
+    fn should_parse_level_file() {
+        // when
+        let level = LevelFile::parse(WALL).unwrap();
+        // then
+        assert_eq!("The Wall", level.name);
+        assert_eq!(Some(Duration::from_millis(500)), level.march);
+        assert_eq!(Some(None), level.bombs);
+        assert_eq!(Some(Pattern::Split), level.pattern);
+        assert_eq!(
+            vec![Cue {
+                at: Duration::from_secs(20),
+                special: Special::Garbage(6)
+            }],
+            level.cues
+        );
+        assert_eq!(5, level.formations[0].len());
+        assert_eq!(Kind::Elite, level.formations[0][2].kind);
+        assert_eq!(vec![(0, 0), (2, 0)], level.bunkers);
+        assert_eq!((3, 2), level.formation_size());
+    }
+
+    #[test]
+    fn should_round_trip_through_text() {
+        // given
+        let level = LevelFile::parse(WALL).unwrap();
+        // when
+        let again = LevelFile::parse(&level.to_text()).unwrap();
+        // then
+        assert_eq!(level, again);
+    }
+
+    #[test]
+    fn should_report_the_line_of_an_error() {
+        assert_eq!(
+            Err(ParseError {
+                line: 3,
+                message: "unknown setting `speed`".into()
+            }),
+            LevelFile::parse("name A\n\nspeed 3\n")
+        );
+        assert_eq!(
+            3,
+            LevelFile::parse("formation\nxxx\nx?x\nend\n")
+                .unwrap_err()
+                .line
+        );
+        assert_eq!(1, LevelFile::parse("formation\nxxx\n").unwrap_err().line);
+        assert!(LevelFile::parse("name Empty\n").is_err());
+    }
+
+    #[test]
+    fn should_reject_times_out_of_range() {
+        for seconds in ["-1", "NaN", "inf", "1e30"] {
+            let text = format!("name A\nat {} garbage 2\n", seconds);
+            assert_eq!(2, LevelFile::parse(&text).unwrap_err().line);
+        }
+        let text = "name A\nat NaN powerup shield\n";
+        assert_eq!(2, LevelFile::parse(text).unwrap_err().line);
+    }
+
+    #[test]
+    fn should_reject_speed_curves_out_of_range() {
+        for curve in ["1.5 1", "-0.1 1", "NaN 1", "0.5 0", "0.5 -2", "0.5 inf"] {
+            let text = format!("name A\ncurve {}\n", curve);
+            assert_eq!(2, LevelFile::parse(&text).unwrap_err().line);
+        }
+    }
+
+    #[test]
+    fn should_number_levels_by_file_name() {
+        assert_eq!(Some(3), level_number(Path::new("pack/level03.txt")));
+        assert_eq!(None, level_number(Path::new("pack/notes.txt")));
+        assert_eq!(None, level_number(Path::new("pack/level1000.txt")));
+    }
+}
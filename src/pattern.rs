@@ -0,0 +1,77 @@
+// How a level's army moves. Every pattern still marches the formation towards the ships;
+// they differ in what happens on the way down.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Pattern {
+    #[default]
+    Classic, // Side to side as one block, a row down at each wall
+    Sway,     // Classic, with a sine wave rippling through the columns
+    Split,    // Front and back halves march in opposite directions
+    Dive,     // Front-line invaders peel off, dive at a ship and fly back
+    Kamikaze, // Front-line invaders peel off and home in on the nearest ship
+}
+
+// Levels before this one always march classically
+const PATTERNS_FROM: u16 = 5;
+
+impl Pattern {
+    pub const ALL: [Pattern; 5] = [
+        Pattern::Classic,
+        Pattern::Sway,
+        Pattern::Split,
+        Pattern::Dive,
+        Pattern::Kamikaze,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Classic => "classic",
+            Pattern::Sway => "sway",
+            Pattern::Split => "split",
+            Pattern::Dive => "dive",
+            Pattern::Kamikaze => "kamikaze",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Pattern::ALL
+            .into_iter()
+            .find(|pattern| pattern.name() == name)
+    }
+
+    // The procedural pick: classic to start with, then every pattern in turn, level by level
+    pub fn for_level(level: u16) -> Self {
+        if level < PATTERNS_FROM {
+            return Pattern::Classic;
+        }
+        Pattern::ALL[level as usize % Pattern::ALL.len()]
+    }
+
+    // Whether invaders leave the formation to attack on their own
+    pub fn sorties(&self) -> bool {
+        matches!(self, Pattern::Dive | Pattern::Kamikaze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_cycle_through_patterns_after_the_first_levels() {
+        // when
+        let patterns: Vec<Pattern> = (1..=9).map(Pattern::for_level).collect();
+        // then
+        assert_eq!([Pattern::Classic; 5], patterns[..5]);
+        assert_eq!(
+            [
+                Pattern::Sway,
+                Pattern::Split,
+                Pattern::Dive,
+                Pattern::Kamikaze
+            ],
+            patterns[5..]
+        );
+        assert_eq!(Some(Pattern::Dive), Pattern::parse("dive"));
+        assert_eq!(None, Pattern::parse("loop"));
+    }
+}
@@ -1,8 +1,8 @@
 use crate::{
     difficulty::Difficulty,
     frame::{Drawable, Frame},
-    invaders::Invaders,
-    shot::Shot,
+    powerup::{Effect, PowerUp},
+    shot::{Owner, Projectiles, Shot, Velocity, SHOT_INTERVAL},
 };
 use rusty_time::Timer;
 use std::time::Duration;
@@ -10,19 +10,30 @@ use std::time::Duration;
 pub struct Player {
     x: usize,
     y: usize,
-    shots: Vec<Shot>,
     fire_rate_timer: Timer,
+    fire_rate: Duration, // Time between shots without power-ups
     max_shots: usize,
+    effects: Vec<Effect>, // Power-ups currently active
+    glyph: char,
+    points: u32, // Points from invaders this ship's shots have hit
 }
 
 impl Player {
     pub fn new(difficulty: &Difficulty) -> Self {
+        Self::with_glyph(difficulty, 'A')
+    }
+
+    // A ship drawn with its own glyph, to tell players apart in co-op
+    pub fn with_glyph(difficulty: &Difficulty, glyph: char) -> Self {
         Self {
             x: 0, // Will be centered dynamically
             y: 0, // Will be set based on frame size
-            shots: Vec::new(),
             fire_rate_timer: Timer::new(difficulty.player_fire_rate),
+            fire_rate: difficulty.player_fire_rate,
             max_shots: difficulty.max_shots.unwrap_or(2),
+            effects: Vec::new(),
+            glyph,
+            points: 0,
         }
     }
 
@@ -31,6 +42,56 @@ impl Player {
         self.y = frame[0].len() - 3;         // Position near the bottom
     }
 
+    // Center ship `index` of `count` within its own slice of the screen width
+    pub fn spread(&mut self, frame: &Frame, index: usize, count: usize) {
+        self.center(frame);
+        self.x = frame.len() * (2 * index + 1) / (2 * count.max(1));
+    }
+
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    pub fn glyph(&self) -> char {
+        self.glyph
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    // Credit this ship with points from an invader its shot destroyed
+    pub fn credit(&mut self, points: u32) {
+        self.points += points;
+    }
+
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    pub fn has(&self, kind: PowerUp) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    // Activate a power-up, or restart its clock if it's already active
+    pub fn power_up(&mut self, kind: PowerUp) {
+        match self.effects.iter_mut().find(|effect| effect.kind == kind) {
+            Some(effect) => effect.timer.reset(),
+            None => self.effects.push(Effect::new(kind)),
+        }
+    }
+
+    // Spend an active shield, returning false if there wasn't one
+    pub fn use_shield(&mut self) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|effect| effect.kind != PowerUp::Shield);
+        self.effects.len() < before
+    }
+
     pub fn move_left(&mut self, frame: &Frame) {
         if self.x <= 1 {
             self.x = frame.len() - 2; // Wrap around
@@ -47,9 +108,26 @@ impl Player {
         }
     }
 
-    pub fn shoot(&mut self) -> bool {
-        if self.shots.len() < self.max_shots && self.fire_rate_timer.finished() {
-            self.shots.push(Shot::new(self.x, self.y - 1));
+    // Fire as `owner` if the fire rate and the shots already in flight allow it
+    pub fn shoot(&mut self, owner: Owner, projectiles: &mut Projectiles) -> bool {
+        let max_shots = self.max_shots + if self.has(PowerUp::MultiShot) { 2 } else { 0 };
+        if projectiles.in_flight(owner) < max_shots && self.fire_rate_timer.finished() {
+            let columns = if self.has(PowerUp::Spread) {
+                self.x.saturating_sub(1)..=self.x + 1
+            } else {
+                self.x..=self.x
+            };
+            for x in columns {
+                let mut shot = Shot::new(x, self.y - 1, owner, Velocity::up(SHOT_INTERVAL));
+                shot.piercing = self.has(PowerUp::Piercing);
+                projectiles.fire(shot);
+            }
+            let fire_rate = if self.has(PowerUp::RapidFire) {
+                self.fire_rate / 2
+            } else {
+                self.fire_rate
+            };
+            self.fire_rate_timer.set_duration(fire_rate);
             self.fire_rate_timer.reset();
             true
         } else {
@@ -57,26 +135,20 @@ impl Player {
         }
     }
 
-    pub fn update(&mut self, delta: Duration) {
+    // Advance the fire rate and power-up clocks, returning the power-ups that just wore off
+    pub fn update(&mut self, delta: Duration) -> Vec<PowerUp> {
         self.fire_rate_timer.tick(delta);
-        for shot in self.shots.iter_mut() {
-            shot.update(delta);
+        for effect in self.effects.iter_mut() {
+            effect.timer.tick(delta);
         }
-        self.shots.retain(|shot| !shot.dead());
-    }
-
-    pub fn detect_hits(&mut self, invaders: &mut Invaders) -> u16 {
-        let mut hit_something = 0u16;
-        for shot in self.shots.iter_mut() {
-            if !shot.exploding {
-                let hit_count = invaders.kill_invader_at(shot.x, shot.y);
-                if hit_count > 0 {
-                    hit_something += hit_count;
-                    shot.explode();
-                }
-            }
-        }
-        hit_something
+        let expired = self
+            .effects
+            .iter()
+            .filter(|effect| effect.timer.finished())
+            .map(|effect| effect.kind)
+            .collect();
+        self.effects.retain(|effect| !effect.timer.finished());
+        expired
     }
 }
 
@@ -89,10 +161,7 @@ impl Default for Player {
 impl Drawable for Player {
     fn draw(&self, frame: &mut Frame) {
         if self.y < frame[0].len() && self.x < frame.len() {
-            frame[self.x][self.y] = 'A'; // Draw the player
-        }
-        for shot in self.shots.iter() {
-            shot.draw(frame);
+            frame[self.x][self.y] = self.glyph; // Draw the player
         }
     }
 }
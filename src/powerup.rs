@@ -0,0 +1,107 @@
+use crate::frame::{Drawable, Frame};
+use rusty_time::Timer;
+use std::time::Duration;
+
+// Chance that a destroyed invader drops a capsule
+pub const DROP_CHANCE: f32 = 0.08;
+// Time a capsule takes to fall one row
+const FALL_INTERVAL: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerUp {
+    RapidFire, // Halves the time between shots
+    MultiShot, // Two more shots in flight at once
+    Spread,    // Fires three shots side by side
+    Piercing,  // Shots carry on through the invaders they hit
+    Shield,    // Absorbs one invasion instead of losing a life
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 5] = [
+        PowerUp::RapidFire,
+        PowerUp::MultiShot,
+        PowerUp::Spread,
+        PowerUp::Piercing,
+        PowerUp::Shield,
+    ];
+
+    // Letter on the falling capsule
+    pub fn glyph(&self) -> char {
+        match self {
+            PowerUp::RapidFire => 'R',
+            PowerUp::MultiShot => 'M',
+            PowerUp::Spread => 'S',
+            PowerUp::Piercing => 'P',
+            PowerUp::Shield => 'O',
+        }
+    }
+
+    // HUD label, also the name of its pickup sound
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUp::RapidFire => "rapid",
+            PowerUp::MultiShot => "multi",
+            PowerUp::Spread => "spread",
+            PowerUp::Piercing => "pierce",
+            PowerUp::Shield => "shield",
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(match self {
+            PowerUp::RapidFire | PowerUp::MultiShot => 10,
+            PowerUp::Spread | PowerUp::Piercing => 8,
+            PowerUp::Shield => 15,
+        })
+    }
+}
+
+// A power-up falling toward the ships
+pub struct Capsule {
+    pub kind: PowerUp,
+    pub x: usize,
+    pub y: usize,
+    timer: Timer,
+}
+
+impl Capsule {
+    pub fn new(kind: PowerUp, x: usize, y: usize) -> Self {
+        Self {
+            kind,
+            x,
+            y,
+            timer: Timer::new(FALL_INTERVAL),
+        }
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        self.timer.tick(delta);
+        if self.timer.finished() {
+            self.y += 1;
+            self.timer.reset();
+        }
+    }
+}
+
+impl Drawable for Capsule {
+    fn draw(&self, frame: &mut Frame) {
+        if self.x < frame.len() && self.y < frame[0].len() {
+            frame[self.x][self.y] = self.kind.glyph();
+        }
+    }
+}
+
+// A power-up a ship has picked up, until it wears off
+pub struct Effect {
+    pub kind: PowerUp,
+    pub timer: Timer,
+}
+
+impl Effect {
+    pub fn new(kind: PowerUp) -> Self {
+        Self {
+            kind,
+            timer: Timer::new(kind.duration()),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use crossterm::{
     terminal::{size, Clear, ClearType},
     QueueableCommand,
 };
-use std::io::{Stdout, Write};
+use std::io::{self, Stdout, Write};
 
 pub fn render(
     stdout: &mut Stdout,
@@ -14,13 +14,25 @@ pub fn render(
     force: bool,
     last_size: &mut (u16, u16),
 ) {
-    let (term_width, term_height) = size().unwrap_or((0, 0));
+    let term_size = size().unwrap_or((0, 0));
+    render_to(stdout, last_frame, curr_frame, force, term_size, last_size).unwrap();
+}
+
+// Render to any output, for a terminal of the given size, e.g. one at the far end of a socket
+pub fn render_to<W: Write>(
+    stdout: &mut W,
+    last_frame: &Frame,
+    curr_frame: &Frame,
+    force: bool,
+    (term_width, term_height): (u16, u16),
+    last_size: &mut (u16, u16),
+) -> io::Result<()> {
     let resized = (term_width, term_height) != *last_size;
 
     if resized || force {
         *last_size = (term_width, term_height);
-        stdout.queue(SetBackgroundColor(Color::Blue)).unwrap();
-        stdout.queue(Clear(ClearType::All)).unwrap();
+        stdout.queue(SetBackgroundColor(Color::Blue))?;
+        stdout.queue(Clear(ClearType::All))?;
     }
 
     let frame_width = curr_frame.len() as u16;
@@ -36,7 +48,7 @@ pub fn render(
     let renderable_height =
         frame_height
             .min(term_height.saturating_sub(2))
-            .min(if curr_frame.len() > 0 {
+            .min(if !curr_frame.is_empty() {
                 curr_frame[0].len() as u16
             } else {
                 0
@@ -45,45 +57,37 @@ pub fn render(
     let x_offset = ((term_width.saturating_sub(frame_width + 2)) / 2).min(term_width - 1);
     let y_offset = ((term_height.saturating_sub(frame_height + 2)) / 2).min(term_height - 1);
 
-    stdout.queue(SetForegroundColor(Color::White)).unwrap();
+    stdout.queue(SetForegroundColor(Color::White))?;
     if renderable_width > 0 && renderable_height > 0 {
-        stdout.queue(MoveTo(x_offset, y_offset)).unwrap();
-        print!("┏{}┓", "━".repeat(renderable_width as usize));
+        stdout.queue(MoveTo(x_offset, y_offset))?;
+        write!(stdout, "┏{}┓", "━".repeat(renderable_width as usize))?;
 
-        stdout
-            .queue(MoveTo(x_offset, y_offset + renderable_height + 1))
-            .unwrap();
-        print!("┗{}┛", "━".repeat(renderable_width as usize));
+        stdout.queue(MoveTo(x_offset, y_offset + renderable_height + 1))?;
+        write!(stdout, "┗{}┛", "━".repeat(renderable_width as usize))?;
 
         for y in 0..renderable_height {
-            stdout.queue(MoveTo(x_offset, y_offset + y + 1)).unwrap();
-            print!("┃");
-            stdout
-                .queue(MoveTo(x_offset + renderable_width + 1, y_offset + y + 1))
-                .unwrap();
-            print!("┃");
+            stdout.queue(MoveTo(x_offset, y_offset + y + 1))?;
+            write!(stdout, "┃")?;
+            stdout.queue(MoveTo(x_offset + renderable_width + 1, y_offset + y + 1))?;
+            write!(stdout, "┃")?;
         }
     }
 
-    stdout.queue(SetBackgroundColor(Color::Black)).unwrap();
-    stdout.queue(SetForegroundColor(Color::White)).unwrap();
+    stdout.queue(SetBackgroundColor(Color::Black))?;
+    stdout.queue(SetForegroundColor(Color::White))?;
 
     for x in 0..renderable_width as usize {
         for y in 0..renderable_height as usize {
-            if x < curr_frame.len()
-                && y < curr_frame[x].len()
-                && x < last_frame.len()
-                && y < last_frame[x].len()
-            {
-                if curr_frame[x][y] != last_frame[x][y] || force || resized {
-                    stdout
-                        .queue(MoveTo(x as u16 + x_offset + 1, y as u16 + y_offset + 1))
-                        .unwrap();
-                    print!("{}", curr_frame[x][y]);
+            if x < curr_frame.len() && y < curr_frame[x].len() {
+                // Cells outside a differently sized last frame always count as changed
+                let last = last_frame.get(x).and_then(|column| column.get(y));
+                if last != Some(&curr_frame[x][y]) || force || resized {
+                    stdout.queue(MoveTo(x as u16 + x_offset + 1, y as u16 + y_offset + 1))?;
+                    write!(stdout, "{}", curr_frame[x][y])?;
                 }
             }
         }
     }
 
-    stdout.flush().unwrap();
+    stdout.flush()
 }
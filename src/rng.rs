@@ -0,0 +1,91 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Small deterministic PRNG (SplitMix64) so a seed reproduces a whole run
+#[derive(Debug, Clone)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    // Seed from the clock, for runs that don't ask for a specific seed
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        // Keep seeds short enough to read off the game-over screen and type back in
+        Self::new(nanos % 1_000_000)
+    }
+
+    // The seed this generator was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform value in `0..bound`, or 0 when `bound` is 0
+    pub fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            0
+        } else {
+            (self.next_u64() % bound as u64) as usize
+        }
+    }
+
+    // True with the given probability
+    pub fn chance(&mut self, probability: f32) -> bool {
+        ((self.next_u64() >> 40) as f32 / (1u64 << 24) as f32) < probability
+    }
+
+    // Shuffle a slice in place (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    // Independent generator for a subsystem, so it can't disturb other systems' sequences
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.next_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_repeat_sequence_for_same_seed() {
+        // given
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+        // when
+        let a: Vec<u64> = (0..5).map(|_| first.next_u64()).collect();
+        let b: Vec<u64> = (0..5).map(|_| second.next_u64()).collect();
+        // then
+        assert_eq!(a, b);
+        assert_ne!(Rng::new(43).next_u64(), a[0]);
+    }
+
+    #[test]
+    fn should_stay_below_bound() {
+        // given
+        let mut rng = Rng::new(7);
+        // when
+        let actual = (0..1000).map(|_| rng.below(6)).max();
+        // then
+        assert_eq!(Some(5), actual);
+        assert_eq!(0, rng.below(0));
+    }
+}
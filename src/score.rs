@@ -1,8 +1,11 @@
-use crate::frame::{Drawable, Frame};
+use crate::{
+    events::{GameEvent, Subscriber},
+    frame::{Drawable, Frame},
+};
 
 #[derive(Default)]
 pub struct Score {
-    count: u16,
+    count: u32,
 }
 
 impl Score {
@@ -10,9 +13,24 @@ impl Score {
         Self { count: 0 }
     }
 
-    pub fn add_points(&mut self, amount: u16) {
+    pub fn add_points(&mut self, amount: u32) {
         self.count += amount;
     }
+
+    pub fn points(&self) -> u32 {
+        self.count
+    }
+}
+
+impl Subscriber for Score {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::InvaderKilled { points, .. } | GameEvent::BossDefeated { points, .. } => {
+                self.add_points(*points)
+            }
+            _ => {}
+        }
+    }
 }
 
 impl Drawable for Score {
@@ -27,3 +45,23 @@ impl Drawable for Score {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_counting_past_u16() {
+        // given
+        let mut score = Score::new();
+        // when
+        for _ in 0..700 {
+            score.notify(&GameEvent::BossDefeated {
+                ship: 0,
+                points: 100,
+            });
+        }
+        // then
+        assert_eq!(70_000, score.points());
+    }
+}
@@ -0,0 +1,370 @@
+// `invaders serve`: independent games for anyone connecting with `telnet` (or `nc`
+// from a raw-mode terminal), simulated and rendered over each client's socket
+// instead of the local TTY.
+use crate::{
+    controller::{Controller, Keyboard},
+    difficulty::Difficulty,
+    frame::{self, Drawable, Frame},
+    game::{Game, State},
+    render,
+    rng::Rng,
+};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::KeyCode,
+    style::ResetColor,
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    mem,
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+// Telnet command bytes (RFC 854) and the options we negotiate
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31; // Negotiate About Window Size, RFC 1073
+
+// Offering to echo and to drop go-aheads puts the client in character mode,
+// so keys arrive as they're pressed; then ask it to report its window size
+const NEGOTIATION: [u8; 9] = [IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD, IAC, DO, NAWS];
+
+// Terminal size assumed until the client reports one, and the smallest we play on
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+// How long an ESC waits for the rest of an arrow key's sequence before it counts on its own
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(100);
+// How long each loop waits for input, which also paces the simulation
+const TICK: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Key(KeyCode),
+    Resize(u16, u16), // Window width and height from NAWS
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Parse {
+    #[default]
+    Data,
+    Command,    // After IAC
+    Option,     // After WILL, WONT, DO or DONT
+    Sub,        // Inside a subnegotiation
+    SubCommand, // IAC inside a subnegotiation
+    Return,     // After CR, which clients follow with LF or NUL
+    Escape,     // After ESC
+    Csi,        // Inside an ANSI escape sequence
+}
+
+// Turns the client's byte stream into key presses and window sizes
+#[derive(Default)]
+pub struct Telnet {
+    state: Parse,
+    subnegotiation: Vec<u8>,
+    escape: Duration, // Time a trailing ESC has waited for the rest of its sequence
+}
+
+impl Telnet {
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        for &byte in bytes {
+            self.byte(byte, &mut inputs);
+        }
+        inputs
+    }
+
+    // Let `delta` pass with no input. An ESC with nothing after it for long enough
+    // is the key itself, rather than the start of a sequence split across reads.
+    pub fn wait(&mut self, delta: Duration) -> Option<Input> {
+        if self.state != Parse::Escape {
+            return None;
+        }
+        self.escape += delta;
+        if self.escape < ESCAPE_TIMEOUT {
+            return None;
+        }
+        self.state = Parse::Data;
+        Some(Input::Key(KeyCode::Esc))
+    }
+
+    fn byte(&mut self, byte: u8, inputs: &mut Vec<Input>) {
+        match self.state {
+            Parse::Data => match byte {
+                IAC => self.state = Parse::Command,
+                0x1b => {
+                    self.state = Parse::Escape;
+                    self.escape = Duration::ZERO;
+                }
+                b'\r' => {
+                    self.state = Parse::Return;
+                    inputs.push(Input::Key(KeyCode::Enter));
+                }
+                b'\n' => inputs.push(Input::Key(KeyCode::Enter)),
+                0x03 => inputs.push(Input::Key(KeyCode::Esc)), // Ctrl-C
+                b' '..=b'~' => inputs.push(Input::Key(KeyCode::Char(byte as char))),
+                _ => {}
+            },
+            Parse::Return => {
+                self.state = Parse::Data;
+                if byte != b'\n' && byte != 0 {
+                    self.byte(byte, inputs);
+                }
+            }
+            Parse::Command => {
+                self.state = match byte {
+                    WILL | WONT | DO | DONT => Parse::Option,
+                    SB => {
+                        self.subnegotiation.clear();
+                        Parse::Sub
+                    }
+                    _ => Parse::Data, // Escaped 255 or a command we don't use
+                }
+            }
+            Parse::Option => self.state = Parse::Data, // Client's replies need no answer
+            Parse::Sub => match byte {
+                IAC => self.state = Parse::SubCommand,
+                _ => self.subnegotiation.push(byte),
+            },
+            Parse::SubCommand => match byte {
+                SE => {
+                    self.state = Parse::Data;
+                    if let [NAWS, w1, w2, h1, h2, ..] = self.subnegotiation[..] {
+                        inputs.push(Input::Resize(
+                            u16::from_be_bytes([w1, w2]),
+                            u16::from_be_bytes([h1, h2]),
+                        ));
+                    }
+                }
+                _ => {
+                    // Escaped 255 within the size bytes
+                    self.state = Parse::Sub;
+                    self.subnegotiation.push(byte);
+                }
+            },
+            Parse::Escape => match byte {
+                b'[' | b'O' => self.state = Parse::Csi,
+                _ => {
+                    self.state = Parse::Data;
+                    inputs.push(Input::Key(KeyCode::Esc));
+                    self.byte(byte, inputs);
+                }
+            },
+            Parse::Csi => {
+                let code = match byte {
+                    b'A' => KeyCode::Up,
+                    b'B' => KeyCode::Down,
+                    b'C' => KeyCode::Right,
+                    b'D' => KeyCode::Left,
+                    b'0'..=b'9' | b';' => return, // Parameters, keep reading
+                    _ => {
+                        self.state = Parse::Data;
+                        return;
+                    }
+                };
+                self.state = Parse::Data;
+                inputs.push(Input::Key(code));
+            }
+        }
+    }
+}
+
+// Accept connections forever, running each client's game on its own thread
+pub fn serve(port: u16, seed: Option<u64>) -> io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!(
+        "Serving invaders on port {}, try `telnet localhost {}`",
+        port, port
+    );
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| String::from("unknown"), |addr| addr.to_string());
+        thread::spawn(move || {
+            println!("{} connected", peer);
+            match play(stream, seed) {
+                Ok(()) => println!("{} left", peer),
+                Err(error) => println!("{} dropped: {}", peer, error),
+            }
+        });
+    }
+    Ok(())
+}
+
+// One client's session: games back to back until they quit or hang up
+fn play(stream: TcpStream, seed: Option<u64>) -> io::Result<()> {
+    let mut reader = stream.try_clone()?;
+    reader.set_read_timeout(Some(TICK))?;
+    let mut writer = stream;
+    writer.write_all(&NEGOTIATION)?;
+
+    let new_game = |frame: &Frame| {
+        let rng = seed.map_or_else(Rng::from_time, Rng::new);
+        Game::new(&Difficulty::default(), rng, frame)
+    };
+    let mut telnet = Telnet::default();
+    let mut term_size = DEFAULT_SIZE;
+    let mut blank = frame::fit_frame(term_size.0, term_size.1);
+    let mut game = new_game(&blank);
+    let mut keyboard = Keyboard::new();
+    let mut last_frame = blank.clone();
+    let mut last_size = (0, 0);
+    let mut over = false;
+
+    let mut screen = Vec::new();
+    screen.queue(Hide)?;
+    writer.write_all(&mem::take(&mut screen))?;
+
+    let mut instant = Instant::now();
+    loop {
+        let delta = instant.elapsed();
+        instant = Instant::now();
+
+        let mut chunk = [0; 256];
+        let inputs = match reader.read(&mut chunk) {
+            Ok(0) => return Ok(()), // Hung up
+            Ok(read) => telnet.feed(&chunk[..read]),
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                telnet.wait(delta).into_iter().collect()
+            }
+            Err(error) => return Err(error),
+        };
+        for input in inputs {
+            match input {
+                Input::Resize(width, height) => {
+                    // Too small a frame can't fit the army
+                    if width < DEFAULT_SIZE.0 || height < DEFAULT_SIZE.1 {
+                        return too_small(&mut writer, (width, height));
+                    }
+                    term_size = (width, height);
+                    blank = frame::fit_frame(term_size.0, term_size.1);
+                    game.resize(&blank);
+                }
+                Input::Key(KeyCode::Esc | KeyCode::Char('q')) => {
+                    return goodbye(&mut writer, "Thanks for playing!")
+                }
+                Input::Key(_) if over => {
+                    game = new_game(&blank);
+                    keyboard = Keyboard::new();
+                    over = false;
+                }
+                Input::Key(code) => {
+                    keyboard.press(code);
+                }
+            }
+        }
+
+        let mut curr_frame = blank.clone();
+        if !over {
+            let actions = [keyboard.actions(&game, delta)];
+            over = game.step(delta, &actions, &blank).state != State::Playing;
+        }
+        game.draw(&mut curr_frame);
+        if over {
+            let middle = curr_frame[0].len() / 2;
+            frame::draw_centered(&mut curr_frame, middle, "GAME OVER");
+            frame::draw_centered(&mut curr_frame, middle + 2, "ANY KEY: AGAIN  Q: QUIT");
+        }
+
+        // A resized frame has nothing to diff against, so draw it in full
+        let force =
+            curr_frame.len() != last_frame.len() || curr_frame[0].len() != last_frame[0].len();
+        if force {
+            last_frame = blank.clone();
+        }
+        render::render_to(
+            &mut screen,
+            &last_frame,
+            &curr_frame,
+            force,
+            term_size,
+            &mut last_size,
+        )?;
+        writer.write_all(&mem::take(&mut screen))?;
+        last_frame = curr_frame;
+    }
+}
+
+// Hand the client's terminal back in a usable state, with a parting line
+fn goodbye(writer: &mut TcpStream, message: &str) -> io::Result<()> {
+    let mut screen = Vec::new();
+    screen.queue(ResetColor)?;
+    screen.queue(Clear(ClearType::All))?;
+    screen.queue(MoveTo(0, 0))?;
+    screen.queue(Show)?;
+    screen.extend_from_slice(format!("{}\r\n", message).as_bytes());
+    writer.write_all(&screen)
+}
+
+// Turn away a client whose window can't fit the game
+fn too_small(writer: &mut TcpStream, (width, height): (u16, u16)) -> io::Result<()> {
+    let message = format!(
+        "Your terminal is {}x{}, invaders needs at least {}x{}.",
+        width, height, DEFAULT_SIZE.0, DEFAULT_SIZE.1
+    );
+    goodbye(writer, &message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_keys_around_telnet_commands() {
+        // given
+        let mut telnet = Telnet::default();
+        // when
+        let inputs = telnet.feed(&[
+            IAC, WILL, NAWS, b' ', 0x1b, b'[', b'D', b'\r', 0, IAC, IAC, b'q',
+        ]);
+        // then
+        assert_eq!(
+            vec![
+                Input::Key(KeyCode::Char(' ')),
+                Input::Key(KeyCode::Left),
+                Input::Key(KeyCode::Enter),
+                Input::Key(KeyCode::Char('q')),
+            ],
+            inputs
+        );
+    }
+
+    #[test]
+    fn should_report_window_size_split_across_reads() {
+        // given
+        let mut telnet = Telnet::default();
+        // when
+        let first = telnet.feed(&[IAC, SB, NAWS, 0, 132]);
+        let second = telnet.feed(&[0, 40, IAC, SE]);
+        // then
+        assert!(first.is_empty());
+        assert_eq!(vec![Input::Resize(132, 40)], second);
+    }
+
+    #[test]
+    fn should_wait_for_an_arrow_key_split_across_reads() {
+        // given
+        let mut telnet = Telnet::default();
+        // when
+        let first = telnet.feed(&[0x1b]);
+        let waited = telnet.wait(TICK);
+        let second = telnet.feed(b"[D");
+        telnet.feed(&[0x1b]);
+        let lone = telnet.wait(ESCAPE_TIMEOUT);
+        // then
+        assert!(first.is_empty());
+        assert_eq!(None, waited);
+        assert_eq!(vec![Input::Key(KeyCode::Left)], second);
+        assert_eq!(Some(Input::Key(KeyCode::Esc)), lone);
+    }
+}
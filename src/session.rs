@@ -0,0 +1,181 @@
+use crate::{
+    controller::Action,
+    difficulty::Difficulty,
+    frame::{draw_centered, draw_text, Drawable, Frame},
+    game::{Game, State, Tick},
+    level::Level,
+    rng::Rng,
+};
+use rusty_time::Timer;
+use std::time::Duration;
+
+// Pause before a player's turn so the seat can change hands
+const GET_READY: Duration = Duration::from_secs(2);
+
+// The players sharing one playthrough: a solo game, or players taking turns
+// on a single ship, each with their own score, level, lives and wave
+pub struct Session {
+    pub players: Vec<Game>,
+    pub current: usize,
+    ready_timer: Timer,
+}
+
+impl Session {
+    pub fn solo(game: Game) -> Self {
+        let mut ready_timer = Timer::new(GET_READY);
+        ready_timer.set_remaining(Duration::ZERO);
+        Self {
+            players: vec![game],
+            current: 0,
+            ready_timer,
+        }
+    }
+
+    // Players alternate, swapping whenever the active one loses a life.
+    // Every player starts at `level` with the same seed so their waves are identical.
+    pub fn hot_seat(
+        level: Level,
+        players: usize,
+        difficulty: &Difficulty,
+        seed: u64,
+        frame: &Frame,
+    ) -> Self {
+        Self {
+            players: (0..players)
+                .map(|_| Game::starting_at(level.clone(), 1, difficulty, Rng::new(seed), frame))
+                .collect(),
+            current: 0,
+            ready_timer: Timer::new(GET_READY),
+        }
+    }
+
+    // The game of the player currently at the controls
+    pub fn game(&self) -> &Game {
+        &self.players[self.current]
+    }
+
+    pub fn resize(&mut self, frame: &Frame) {
+        for game in self.players.iter_mut() {
+            game.resize(frame);
+        }
+    }
+
+    // Step the active player's game, handing over to the next player after a lost life.
+    // The session is only lost once every player is out of lives.
+    pub fn step(&mut self, delta: Duration, actions: &[Vec<Action>], frame: &Frame) -> Tick {
+        if !self.ready_timer.finished() {
+            self.ready_timer.tick(delta);
+            return Tick::default();
+        }
+
+        let mut tick = self.players[self.current].step(delta, actions, frame);
+        if tick.life_lost && self.players.len() > 1 {
+            if let Some(next) = self.next_player() {
+                self.current = next;
+                self.ready_timer.reset();
+                tick.state = State::Playing;
+            }
+        }
+        tick
+    }
+
+    // Next player in turn order who still has lives left, possibly the current one
+    fn next_player(&self) -> Option<usize> {
+        (1..=self.players.len())
+            .map(|offset| (self.current + offset) % self.players.len())
+            .find(|&index| self.players[index].lives > 0)
+    }
+
+    // Index of the highest scorer, or None on a tie
+    pub fn winner(&self) -> Option<usize> {
+        let best = self.players.iter().map(|game| game.score.points()).max()?;
+        let mut leaders = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, game)| game.score.points() == best);
+        let (index, _) = leaders.next()?;
+        leaders.next().is_none().then_some(index)
+    }
+}
+
+impl Drawable for Session {
+    fn draw(&self, frame: &mut Frame) {
+        self.game().draw(frame);
+        if self.players.len() < 2 {
+            return;
+        }
+
+        // Everyone's score on the second row, with the active player marked
+        let mut x = 0;
+        for (index, game) in self.players.iter().enumerate() {
+            let marker = if index == self.current { '>' } else { ' ' };
+            let text = format!("{}P{}: {:0>4}", marker, index + 1, game.score.points());
+            draw_text(frame, x, 1, &text);
+            x += text.len() + 2;
+        }
+        if !self.ready_timer.finished() {
+            let middle = frame[0].len() / 2;
+            draw_centered(
+                frame,
+                middle,
+                &format!("PLAYER {} - GET READY", self.current + 1),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::sized_frame;
+
+    // Let the army march down unopposed until the active player loses a life
+    fn lose_life(session: &mut Session, frame: &Frame) -> Tick {
+        loop {
+            let tick = session.step(Duration::from_millis(50), &[], frame);
+            if tick.life_lost {
+                return tick;
+            }
+        }
+    }
+
+    #[test]
+    fn should_swap_players_on_lost_life() {
+        // given
+        let frame = sized_frame(40, 12);
+        let mut session = Session::hot_seat(Level::new(), 2, &Difficulty::default(), 1, &frame);
+        // when
+        lose_life(&mut session, &frame);
+        // then
+        assert_eq!(1, session.current);
+        assert_eq!(2, session.players[0].lives);
+        assert_eq!(3, session.players[1].lives);
+    }
+
+    #[test]
+    fn should_end_only_when_every_player_is_out() {
+        // given
+        let frame = sized_frame(40, 12);
+        let mut session = Session::hot_seat(Level::new(), 2, &Difficulty::default(), 1, &frame);
+        // when
+        let states: Vec<State> = (0..6)
+            .map(|_| lose_life(&mut session, &frame).state)
+            .collect();
+        // then
+        assert_eq!(State::Playing, states[4]);
+        assert_eq!(State::Lost, states[5]);
+        assert_eq!(None, session.winner());
+    }
+
+    #[test]
+    fn should_start_every_player_at_the_chosen_level() {
+        // given
+        let frame = sized_frame(40, 12);
+        // when
+        let session =
+            Session::hot_seat(Level::starting_at(7), 2, &Difficulty::default(), 1, &frame);
+        // then
+        assert!(session.players.iter().all(|game| game.level.level() == 7));
+    }
+}
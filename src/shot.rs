@@ -2,37 +2,125 @@ use crate::frame::{Drawable, Frame};
 use rusty_time::Timer;
 use std::time::Duration;
 
+// Time a ship's shot takes to climb one row
+pub const SHOT_INTERVAL: Duration = Duration::from_millis(50);
+// Time an invader's bomb takes to fall one row
+pub const BOMB_INTERVAL: Duration = Duration::from_millis(120);
+// Shots still in flight after this long fizzle out
+const LIFETIME: Duration = Duration::from_secs(5);
+
+// Who fired a shot, which decides what it can hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Owner {
+    Ship(usize), // Index of the ship in the game
+    Invader,
+    Ufo,
+}
+
+// Direction of travel, one cell per step, and the time each step takes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Velocity {
+    pub dx: isize,
+    pub dy: isize,
+    pub interval: Duration,
+}
+
+impl Velocity {
+    pub fn up(interval: Duration) -> Self {
+        Self {
+            dx: 0,
+            dy: -1,
+            interval,
+        }
+    }
+
+    pub fn down(interval: Duration) -> Self {
+        Self {
+            dx: 0,
+            dy: 1,
+            interval,
+        }
+    }
+}
+
 pub struct Shot {
     pub x: usize,
     pub y: usize,
+    pub owner: Owner,
+    pub velocity: Velocity,
+    pub damage: u8,
     pub exploding: bool,
-    timer: Timer,
+    pub piercing: bool,        // Carries on through whatever it hits
+    path: Vec<(usize, usize)>, // Cells crossed since the last update, including the start
+    elapsed: Duration,         // Time towards the next step
+    lifetime: Timer,
+    explosion: Timer,
+    gone: bool, // Left the playfield
 }
 
 impl Shot {
-    pub fn new(x: usize, y: usize) -> Self {
+    pub fn new(x: usize, y: usize, owner: Owner, velocity: Velocity) -> Self {
         Self {
             x,
             y,
+            owner,
+            velocity,
+            damage: 1,
             exploding: false,
-            timer: Timer::new(Duration::from_millis(50)),
+            piercing: false,
+            path: vec![(x, y)],
+            elapsed: Duration::ZERO,
+            lifetime: Timer::new(LIFETIME),
+            explosion: Timer::new(Duration::from_millis(250)),
+            gone: false,
         }
     }
-    pub fn update(&mut self, delta: Duration) {
-        self.timer.tick(delta);
-        if self.timer.finished() && !self.exploding {
-            if self.y > 0 {
-                self.y -= 1; // Move upward
+
+    // Move as many cells as `delta` allows, so fast shots and long ticks never skip cells
+    pub fn update(&mut self, delta: Duration, frame: &Frame) {
+        self.path.clear();
+        self.path.push((self.x, self.y));
+        if self.exploding {
+            self.explosion.tick(delta);
+            return;
+        }
+        self.lifetime.tick(delta);
+        self.elapsed += delta;
+        while self.elapsed >= self.velocity.interval && !self.gone {
+            self.elapsed -= self.velocity.interval;
+            let x = self.x.checked_add_signed(self.velocity.dx);
+            let y = self.y.checked_add_signed(self.velocity.dy);
+            match (x, y) {
+                // Row 0 is the HUD, so shots leave the playfield below it
+                (Some(x), Some(y)) if x < frame.len() && y > 0 && y < frame[0].len() => {
+                    (self.x, self.y) = (x, y);
+                    self.path.push((x, y));
+                }
+                _ => self.gone = true,
+            }
+            if self.velocity.interval.is_zero() {
+                break;
             }
-            self.timer.reset();
         }
     }
-    pub fn explode(&mut self) {
+
+    // Cells the shot swept across during the last update, oldest first
+    pub fn path(&self) -> &[(usize, usize)] {
+        &self.path
+    }
+
+    // Blow up at a cell along the path, which is where the shot stops
+    pub fn explode_at(&mut self, x: usize, y: usize) {
+        (self.x, self.y) = (x, y);
         self.exploding = true;
-        self.timer = Timer::new(Duration::from_millis(250));
     }
+
     pub fn dead(&self) -> bool {
-        (self.exploding && self.timer.finished()) || (self.y == 0)
+        if self.exploding {
+            self.explosion.finished()
+        } else {
+            self.gone || self.lifetime.finished()
+        }
     }
 }
 
@@ -40,49 +128,93 @@ impl Drawable for Shot {
     fn draw(&self, frame: &mut Frame) {
         // Ensure shots are drawn within bounds
         if self.y < frame[0].len() && self.x < frame.len() {
-            frame[self.x][self.y] = if self.exploding { '*' } else { '|' };
+            frame[self.x][self.y] = match (self.exploding, self.owner) {
+                (true, _) => '*',
+                (false, Owner::Ship(_)) => '|',
+                (false, Owner::Invader | Owner::Ufo) => '!',
+            };
         }
     }
 }
 
-pub struct ShotManager {
+// Every shot in flight, whoever fired it
+#[derive(Default)]
+pub struct Projectiles {
     shots: Vec<Shot>,
-    fire_rate_timer: Timer,
 }
 
-impl ShotManager {
-    pub fn new() -> Self {
-        Self {
-            shots: Vec::new(),
-            fire_rate_timer: Timer::new(Duration::from_millis(333)), // Limit to 3 shots per second
-        }
+impl Projectiles {
+    pub fn fire(&mut self, shot: Shot) {
+        self.shots.push(shot);
     }
 
-    pub fn try_fire_shot(&mut self, x: usize, y: usize, max_shots: usize) {
-        // Check if we can fire a new shot based on the fire rate timer
-        if self.shots.len() < max_shots && self.fire_rate_timer.finished() {
-            self.shots.push(Shot::new(x, y));
-            self.fire_rate_timer.reset();
-        }
+    // Shots of one owner still on screen, exploding or not
+    pub fn in_flight(&self, owner: Owner) -> usize {
+        self.shots.iter().filter(|shot| shot.owner == owner).count()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Shot> {
+        self.shots.iter()
     }
 
-    pub fn update(&mut self, delta: Duration) {
-        // Update the fire rate timer
-        self.fire_rate_timer.tick(delta);
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Shot> {
+        self.shots.iter_mut()
+    }
 
-        // Update each shot and remove any that are dead
-        for shot in &mut self.shots {
-            shot.update(delta);
+    pub fn update(&mut self, delta: Duration, frame: &Frame) {
+        for shot in self.shots.iter_mut() {
+            shot.update(delta, frame);
         }
         self.shots.retain(|shot| !shot.dead());
     }
+
+    pub fn clear(&mut self) {
+        self.shots.clear();
+    }
 }
 
-impl Drawable for ShotManager {
+impl Drawable for Projectiles {
     fn draw(&self, frame: &mut Frame) {
-        // Draw each active shot onto the frame
-        for shot in &self.shots {
+        for shot in self.shots.iter() {
             shot.draw(frame);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::sized_frame;
+
+    #[test]
+    fn should_sweep_every_cell_crossed_in_a_long_tick() {
+        // given
+        let frame = sized_frame(10, 10);
+        let mut shot = Shot::new(4, 8, Owner::Ship(0), Velocity::up(SHOT_INTERVAL));
+        // when
+        shot.update(SHOT_INTERVAL * 3, &frame);
+        // then
+        assert_eq!(&[(4, 8), (4, 7), (4, 6), (4, 5)], shot.path());
+        assert_eq!((4, 5), (shot.x, shot.y));
+    }
+
+    #[test]
+    fn should_leave_the_playfield_below_the_hud() {
+        // given
+        let frame = sized_frame(10, 10);
+        let mut projectiles = Projectiles::default();
+        projectiles.fire(Shot::new(4, 2, Owner::Ship(0), Velocity::up(SHOT_INTERVAL)));
+        projectiles.fire(Shot::new(
+            4,
+            8,
+            Owner::Invader,
+            Velocity::down(BOMB_INTERVAL),
+        ));
+        // when
+        projectiles.update(SHOT_INTERVAL * 2, &frame);
+        projectiles.update(BOMB_INTERVAL * 2, &frame);
+        // then
+        assert_eq!(0, projectiles.in_flight(Owner::Ship(0)));
+        assert_eq!(0, projectiles.in_flight(Owner::Invader));
+    }
+}
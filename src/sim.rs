@@ -0,0 +1,319 @@
+// Headless batch simulation of bot games, for comparing difficulty balance changes
+use crate::{
+    controller::{Autopilot, Controller, RandomBot},
+    difficulty::{Difficulty, DifficultyLevel},
+    frame::sized_frame,
+    game::{Game, State},
+    rng::Rng,
+};
+use std::{fmt::Write, thread, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bot {
+    Greedy, // The attract-mode autopilot
+    Random, // Mashes random keys, as a baseline
+}
+
+impl Bot {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "greedy" => Some(Bot::Greedy),
+            "random" => Some(Bot::Random),
+            _ => None,
+        }
+    }
+
+    fn controller(&self, seed: u64) -> Box<dyn Controller> {
+        match self {
+            Bot::Greedy => Box::new(Autopilot::default()),
+            Bot::Random => Box::new(RandomBot::new(Rng::new(seed))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimConfig {
+    pub games: usize,
+    pub difficulty: DifficultyLevel,
+    pub bot: Bot,
+    pub seed: u64,            // Game `i` is played with seed `seed + i`
+    pub threads: usize,       // 0 uses every available core
+    pub time_limit: Duration, // Simulated time after which a game is abandoned
+    pub csv: bool,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            games: 100,
+            difficulty: DifficultyLevel::Normal,
+            bot: Bot::Greedy,
+            seed: 0,
+            threads: 0,
+            time_limit: Duration::from_secs(600),
+            csv: false,
+        }
+    }
+}
+
+// Fixed simulation timestep and playfield, roughly what a terminal game runs at
+const TICK: Duration = Duration::from_millis(16);
+const WIDTH: usize = 60;
+const HEIGHT: usize = 30;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameStats {
+    pub won: bool,
+    pub level: u16, // Level the game ended on
+    pub shots: u32,
+    pub kills: u32,
+    pub clear_times: Vec<Duration>, // Time taken to clear each level, in order
+}
+
+// Play a single bot game to completion or the time limit
+pub fn play(config: &SimConfig, seed: u64) -> GameStats {
+    let frame = sized_frame(WIDTH, HEIGHT);
+    let mut game = Game::new(&Difficulty::new(config.difficulty), Rng::new(seed), &frame);
+    let mut bot = config.bot.controller(seed);
+    let mut stats = GameStats {
+        won: false,
+        level: 1,
+        shots: 0,
+        kills: 0,
+        clear_times: Vec::new(),
+    };
+
+    let mut elapsed = Duration::ZERO;
+    let mut level_started = Duration::ZERO;
+    while elapsed < config.time_limit {
+        let actions = bot.actions(&game, TICK);
+        let tick = game.step(TICK, &[actions], &frame);
+        elapsed += TICK;
+        if tick.level_cleared || tick.state == State::Won {
+            stats.clear_times.push(elapsed - level_started);
+            level_started = elapsed;
+        }
+        if tick.state != State::Playing {
+            stats.won = tick.state == State::Won;
+            break;
+        }
+    }
+    stats.level = game.level.level();
+    (stats.shots, stats.kills) = (game.tally.shots, game.tally.kills);
+    stats
+}
+
+// Play every game of the batch, spread across threads
+pub fn run(config: &SimConfig) -> Vec<GameStats> {
+    let threads = match config.threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(config.games.max(1));
+
+    let mut results: Vec<(usize, GameStats)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                scope.spawn(move || {
+                    (worker..config.games)
+                        .step_by(threads)
+                        .map(|i| (i, play(config, config.seed.wrapping_add(i as u64))))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("simulation thread panicked"))
+            .collect()
+    });
+    // Keep results in seed order so runs are comparable regardless of thread count
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, stats)| stats).collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LevelSummary {
+    pub level: usize,
+    pub cleared: usize, // Games that cleared this level
+    pub mean_time: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub games: usize,
+    pub wins: usize,
+    pub mean_level: f64,
+    pub accuracy: f64, // Kills per shot fired
+    pub levels: Vec<LevelSummary>,
+}
+
+impl Summary {
+    pub fn new(stats: &[GameStats]) -> Self {
+        let games = stats.len();
+        let shots: u64 = stats.iter().map(|game| game.shots as u64).sum();
+        let kills: u64 = stats.iter().map(|game| game.kills as u64).sum();
+        let deepest = stats
+            .iter()
+            .map(|game| game.clear_times.len())
+            .max()
+            .unwrap_or(0);
+        let levels = (0..deepest)
+            .map(|index| {
+                let times: Vec<Duration> = stats
+                    .iter()
+                    .filter_map(|game| game.clear_times.get(index).copied())
+                    .collect();
+                LevelSummary {
+                    level: index + 1,
+                    cleared: times.len(),
+                    mean_time: times.iter().sum::<Duration>() / times.len() as u32,
+                }
+            })
+            .collect();
+        Self {
+            games,
+            wins: stats.iter().filter(|game| game.won).count(),
+            mean_level: stats.iter().map(|game| game.level as f64).sum::<f64>()
+                / games.max(1) as f64,
+            accuracy: if shots == 0 {
+                0.0
+            } else {
+                kills as f64 / shots as f64
+            },
+            levels,
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    pub fn table(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "games       {}", self.games);
+        let _ = writeln!(out, "win rate    {:.1}%", self.win_rate() * 100.0);
+        let _ = writeln!(out, "mean level  {:.2}", self.mean_level);
+        let _ = writeln!(out, "accuracy    {:.1}%", self.accuracy * 100.0);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "level  cleared  mean time-to-clear");
+        for level in &self.levels {
+            let _ = writeln!(
+                out,
+                "{:>5}  {:>7}  {:>17.1}s",
+                level.level,
+                level.cleared,
+                level.mean_time.as_secs_f64()
+            );
+        }
+        out
+    }
+
+    // A totals row with the level columns left empty, so a batch that cleared nothing still
+    // reports, then per-level rows with the totals repeated on each for easy spreadsheet pivots
+    pub fn csv(&self) -> String {
+        let mut out =
+            String::from("games,win_rate,mean_level,accuracy,level,cleared,mean_clear_secs\n");
+        let _ = writeln!(
+            out,
+            "{},{:.4},{:.4},{:.4},,,",
+            self.games,
+            self.win_rate(),
+            self.mean_level,
+            self.accuracy
+        );
+        for level in &self.levels {
+            let _ = writeln!(
+                out,
+                "{},{:.4},{:.4},{:.4},{},{},{:.3}",
+                self.games,
+                self.win_rate(),
+                self.mean_level,
+                self.accuracy,
+                level.level,
+                level.cleared,
+                level.mean_time.as_secs_f64()
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_give_same_results_regardless_of_thread_count() {
+        // given
+        let config = SimConfig {
+            games: 4,
+            time_limit: Duration::from_secs(30),
+            ..SimConfig::default()
+        };
+        // when
+        let single = run(&SimConfig {
+            threads: 1,
+            ..config.clone()
+        });
+        let parallel = run(&SimConfig {
+            threads: 3,
+            ..config
+        });
+        // then
+        assert_eq!(single, parallel);
+    }
+
+    #[test]
+    fn should_summarize_per_level_clear_times() {
+        // given
+        let game = |won, clear_times: Vec<u64>| GameStats {
+            won,
+            level: clear_times.len() as u16 + 1,
+            shots: 10,
+            kills: 5,
+            clear_times: clear_times.into_iter().map(Duration::from_secs).collect(),
+        };
+        // when
+        let summary = Summary::new(&[game(false, vec![10, 20]), game(true, vec![30])]);
+        // then
+        assert_eq!(0.5, summary.win_rate());
+        assert_eq!(2.5, summary.mean_level);
+        assert_eq!(0.5, summary.accuracy);
+        assert_eq!(
+            vec![
+                LevelSummary {
+                    level: 1,
+                    cleared: 2,
+                    mean_time: Duration::from_secs(20)
+                },
+                LevelSummary {
+                    level: 2,
+                    cleared: 1,
+                    mean_time: Duration::from_secs(20)
+                },
+            ],
+            summary.levels
+        );
+    }
+
+    #[test]
+    fn should_report_totals_when_no_level_was_cleared() {
+        // given
+        let lost = GameStats {
+            won: false,
+            level: 1,
+            shots: 4,
+            kills: 1,
+            clear_times: Vec::new(),
+        };
+        // when
+        let csv = Summary::new(&[lost.clone(), lost]).csv();
+        // then
+        assert_eq!(
+            "games,win_rate,mean_level,accuracy,level,cleared,mean_clear_secs\n\
+             2,0.0000,1.0000,0.2500,,,\n",
+            csv
+        );
+    }
+}
@@ -0,0 +1,346 @@
+// Spectator stream: `--broadcast ADDR` publishes every rendered frame, and
+// `invaders watch ADDR` shows them read-only in another terminal. Addresses
+// containing a `/` are Unix socket paths, anything else is a TCP address.
+//
+// Spectators get a full frame when they join, then only the cells that changed:
+//   FULL <width> <height>   followed by `height` rows of `width` characters
+//   DIFF <count>            followed by `count` lines of `<x> <y> <char>`
+use crate::frame::{sized_frame, Frame};
+#[cfg(unix)]
+use std::os::unix::{
+    fs::FileTypeExt,
+    net::{UnixListener, UnixStream},
+};
+use std::{
+    fs,
+    io::{self, BufRead, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    time::Duration,
+};
+
+// Spectators that can't keep up for this long are dropped rather than stalling the game
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Update {
+    Full(Frame),
+    Diff(Vec<(usize, usize, char)>),
+}
+
+impl Update {
+    // What changed between two frames; a full frame if the size changed
+    pub fn between(last: &Frame, curr: &Frame) -> Self {
+        if last.len() != curr.len() || last.first().map(Vec::len) != curr.first().map(Vec::len) {
+            return Update::Full(curr.clone());
+        }
+        let mut changes = Vec::new();
+        for (x, (old, new)) in last.iter().zip(curr.iter()).enumerate() {
+            for (y, (&before, &after)) in old.iter().zip(new.iter()).enumerate() {
+                if before != after {
+                    changes.push((x, y, after));
+                }
+            }
+        }
+        Update::Diff(changes)
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            Update::Full(frame) => {
+                let height = frame.first().map_or(0, Vec::len);
+                let mut out = format!("FULL {} {}\n", frame.len(), height);
+                for y in 0..height {
+                    out.extend(frame.iter().map(|column| column[y]));
+                    out.push('\n');
+                }
+                out
+            }
+            Update::Diff(changes) => {
+                let mut out = format!("DIFF {}\n", changes.len());
+                for (x, y, c) in changes {
+                    out += &format!("{} {} {}\n", x, y, c);
+                }
+                out
+            }
+        }
+    }
+
+    // Read the next update, or None once the broadcast has ended
+    pub fn read<R: BufRead>(reader: &mut R) -> io::Result<Option<Self>> {
+        let invalid = |what: &str| io::Error::new(ErrorKind::InvalidData, what.to_string());
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let update = match words[..] {
+            ["FULL", width, height] => {
+                let width: usize = width.parse().map_err(|_| invalid("bad frame width"))?;
+                let height: usize = height.parse().map_err(|_| invalid("bad frame height"))?;
+                let mut frame = sized_frame(width, height);
+                for y in 0..height {
+                    line.clear();
+                    reader.read_line(&mut line)?;
+                    let cells = line.trim_end_matches('\n').chars();
+                    for (column, c) in frame.iter_mut().zip(cells) {
+                        column[y] = c;
+                    }
+                }
+                Update::Full(frame)
+            }
+            ["DIFF", count] => {
+                let count: usize = count.parse().map_err(|_| invalid("bad diff length"))?;
+                let mut changes = Vec::with_capacity(count);
+                for _ in 0..count {
+                    line.clear();
+                    reader.read_line(&mut line)?;
+                    // The character may itself be a space, so split no further than it
+                    let mut parts = line.trim_end_matches('\n').splitn(3, ' ');
+                    let mut number = || parts.next().and_then(|part| part.parse().ok());
+                    let (Some(x), Some(y)) = (number(), number()) else {
+                        return Err(invalid("bad diff cell"));
+                    };
+                    let c = parts.next().and_then(|part| part.chars().next());
+                    changes.push((x, y, c.ok_or_else(|| invalid("bad diff cell"))?));
+                }
+                Update::Diff(changes)
+            }
+            _ => return Err(invalid("unknown update")),
+        };
+        Ok(Some(update))
+    }
+
+    // Bring a spectator's copy of the frame up to date
+    pub fn apply(self, frame: &mut Frame) {
+        match self {
+            Update::Full(full) => *frame = full,
+            Update::Diff(changes) => {
+                for (x, y, c) in changes {
+                    if let Some(cell) = frame.get_mut(x).and_then(|column| column.get_mut(y)) {
+                        *cell = c;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Accepts readers on a Unix socket path or a TCP address, without blocking.
+// Also used to export live game state for overlays.
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    pub fn bind(address: &str) -> io::Result<Self> {
+        if address.contains('/') {
+            return Self::bind_unix(address);
+        }
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Listener::Tcp(listener))
+    }
+
+    #[cfg(unix)]
+    fn bind_unix(path: &str) -> io::Result<Self> {
+        // Clear a socket left behind by a previous run, but never anything else
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::AddrInUse,
+                    format!("{} already exists and isn't a socket", path),
+                ))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Listener::Unix(listener, PathBuf::from(path)))
+    }
+
+    #[cfg(not(unix))]
+    fn bind_unix(_path: &str) -> io::Result<Self> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "Unix sockets aren't available here, use a TCP address instead",
+        ))
+    }
+
+    // Readers waiting to join, set up for blocking writes
+    pub fn accept(&self) -> Vec<Box<dyn Write + Send>> {
+        let mut joined: Vec<Box<dyn Write + Send>> = Vec::new();
+        match self {
+            Listener::Tcp(listener) => {
+                while let Ok((stream, _)) = listener.accept() {
+                    if stream.set_nonblocking(false).is_ok()
+                        && stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok()
+                    {
+                        joined.push(Box::new(stream));
+                    }
+                }
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                while let Ok((stream, _)) = listener.accept() {
+                    if stream.set_nonblocking(false).is_ok()
+                        && stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok()
+                    {
+                        joined.push(Box::new(stream));
+                    }
+                }
+            }
+        }
+        joined
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = self {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+// Publishes frames to however many spectators are connected
+pub struct Broadcaster {
+    listener: Listener,
+    spectators: Vec<Box<dyn Write + Send>>,
+    last: Option<Frame>,
+}
+
+impl Broadcaster {
+    pub fn bind(address: &str) -> io::Result<Self> {
+        Ok(Self {
+            listener: Listener::bind(address)?,
+            spectators: Vec::new(),
+            last: None,
+        })
+    }
+
+    // Send a rendered frame: the full frame to spectators who just joined, the changes to the rest
+    pub fn publish(&mut self, frame: &Frame) {
+        let update = match &self.last {
+            Some(last) => Update::between(last, frame),
+            None => Update::Full(frame.clone()),
+        };
+        if update != Update::Diff(Vec::new()) {
+            let message = update.encode();
+            self.spectators
+                .retain_mut(|spectator| spectator.write_all(message.as_bytes()).is_ok());
+        }
+
+        let joined = self.listener.accept();
+        if !joined.is_empty() {
+            let message = Update::Full(frame.clone()).encode();
+            self.spectators
+                .extend(joined.into_iter().filter_map(|mut spectator| {
+                    spectator.write_all(message.as_bytes()).ok()?;
+                    Some(spectator)
+                }));
+        }
+        self.last = Some(frame.clone());
+    }
+}
+
+// Connect to a broadcast, for `invaders watch`
+pub fn connect(address: &str) -> io::Result<Box<dyn Read + Send>> {
+    if address.contains('/') {
+        #[cfg(unix)]
+        return Ok(Box::new(UnixStream::connect(address)?));
+        #[cfg(not(unix))]
+        return Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "Unix sockets aren't available here, watch a TCP address instead",
+        ));
+    }
+    Ok(Box::new(TcpStream::connect(address)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::draw_text;
+    use std::io::{BufReader, Cursor};
+
+    fn frame(text: &str) -> Frame {
+        let mut frame = sized_frame(6, 2);
+        draw_text(&mut frame, 0, 1, text);
+        frame
+    }
+
+    #[test]
+    fn should_round_trip_updates() {
+        // given
+        let updates = vec![
+            Update::Full(frame("A  x |")),
+            Update::between(&frame("A  x |"), &frame(" A x  ")),
+        ];
+        // when
+        let encoded: String = updates.iter().map(Update::encode).collect();
+        let mut reader = Cursor::new(encoded);
+        let mut decoded = Vec::new();
+        while let Some(update) = Update::read(&mut reader).unwrap() {
+            decoded.push(update);
+        }
+        // then
+        assert_eq!(updates, decoded);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_send_late_joiners_a_full_frame() {
+        // given
+        let path = std::env::temp_dir().join(format!("invaders-test-{}.sock", std::process::id()));
+        let address = path.to_str().unwrap();
+        let mut broadcaster = Broadcaster::bind(address).unwrap();
+        broadcaster.publish(&frame("A     "));
+        let mut early = BufReader::new(connect(address).unwrap());
+        broadcaster.publish(&frame(" A    "));
+        let mut late = BufReader::new(connect(address).unwrap());
+        // when
+        broadcaster.publish(&frame("  A   "));
+        // then
+        let mut seen = sized_frame(6, 2);
+        for _ in 0..2 {
+            Update::read(&mut early).unwrap().unwrap().apply(&mut seen);
+        }
+        assert_eq!(frame("  A   "), seen);
+        assert_eq!(
+            Some(Update::Full(frame("  A   "))),
+            Update::read(&mut late).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_leave_other_files_at_the_path_alone() {
+        // given
+        let path = std::env::temp_dir().join(format!("invaders-notes-{}.txt", std::process::id()));
+        fs::write(&path, "notes").unwrap();
+        // when
+        let bound = Listener::bind(path.to_str().unwrap());
+        // then
+        assert_eq!(ErrorKind::AddrInUse, bound.err().unwrap().kind());
+        assert_eq!("notes", fs::read_to_string(&path).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_replace_a_stale_socket() {
+        // given
+        let path = std::env::temp_dir().join(format!("invaders-stale-{}.sock", std::process::id()));
+        drop(UnixListener::bind(&path).unwrap());
+        // when
+        let bound = Listener::bind(path.to_str().unwrap());
+        // then
+        assert!(bound.is_ok());
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    error::Error,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub const SAMPLE_RATE: u32 = 22_050;
+
+// Semitones between consecutive pitch variants of an effect
+const PITCH_STEP: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    Square,
+    Noise,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundSpec {
+    pub name: String,
+    pub wave: Waveform,
+    pub start_hz: f32,
+    pub end_hz: f32,
+    pub duration: Duration,
+    pub volume: f32,
+    pub duty: f32,
+    pub warble_hz: f32,    // Vibrato rate, 0 disables it
+    pub warble_depth: f32, // Vibrato depth as a fraction of the frequency
+    pub pitches: usize,    // How many pitch variants to render
+}
+
+impl SoundSpec {
+    // Copy of this spec shifted up by `step` pitch steps
+    pub fn variant(&self, step: usize) -> Self {
+        let ratio = 2f32.powf(step as f32 * PITCH_STEP / 12.0);
+        Self {
+            name: variant_name(&self.name, step),
+            start_hz: self.start_hz * ratio,
+            end_hz: self.end_hz * ratio,
+            pitches: 1,
+            ..self.clone()
+        }
+    }
+
+    // Render the effect as signed 16-bit mono samples
+    pub fn samples(&self) -> Vec<i16> {
+        let count = (self.duration.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+        let mut samples = Vec::with_capacity(count);
+        let mut phase = 0f32;
+        let mut lfsr: u16 = 1;
+        let mut noise_level = 1f32;
+
+        for i in 0..count {
+            let t = i as f32 / count as f32;
+            let seconds = i as f32 / SAMPLE_RATE as f32;
+            let warble =
+                1.0 + self.warble_depth * (seconds * self.warble_hz * std::f32::consts::TAU).sin();
+            let freq = (self.start_hz + (self.end_hz - self.start_hz) * t) * warble;
+
+            phase += freq / SAMPLE_RATE as f32;
+            let level = match self.wave {
+                Waveform::Square => {
+                    if phase.fract() < self.duty {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Waveform::Noise => {
+                    // Clock a 15-bit LFSR at the effect frequency, like the NES noise channel
+                    while phase >= 1.0 {
+                        phase -= 1.0;
+                        let bit = (lfsr ^ (lfsr >> 1)) & 1;
+                        lfsr = (lfsr >> 1) | (bit << 14);
+                        noise_level = if lfsr & 1 == 0 { 1.0 } else { -1.0 };
+                    }
+                    noise_level
+                }
+            };
+            let envelope = self.volume * (1.0 - t);
+            samples.push((level * envelope * i16::MAX as f32) as i16);
+        }
+        samples
+    }
+
+    // Render the effect as a complete WAV file
+    pub fn wav(&self) -> Vec<u8> {
+        let samples = self.samples();
+        let data_len = (samples.len() * 2) as u32;
+        let mut wav = Vec::with_capacity(44 + data_len as usize);
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // Mono
+        wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // Byte rate
+        wav.extend_from_slice(&2u16.to_le_bytes()); // Block align
+        wav.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        wav
+    }
+}
+
+// Name under which the `step`th pitch variant of an effect is registered
+pub fn variant_name(name: &str, step: usize) -> String {
+    if step == 0 {
+        name.to_string()
+    } else {
+        format!("{}+{}", name, step)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+pub struct SoundPack {
+    pub specs: Vec<SoundSpec>,
+}
+
+impl SoundPack {
+    // Parse a sound pack description, see `sounds/chiptune.txt` for the format
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut specs = Vec::new();
+        for (index, raw) in source.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let spec = parse_spec(line).map_err(|message| ParseError {
+                line: index + 1,
+                message,
+            })?;
+            specs.push(spec);
+        }
+        Ok(Self { specs })
+    }
+
+    // The pack shipped with the game
+    pub fn builtin() -> Self {
+        Self::parse(include_str!("../sounds/chiptune.txt")).expect("built-in sound pack is valid")
+    }
+
+    // Write every effect and pitch variant into `dir`, returning the clip names and file paths
+    pub fn render(&self, dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+        fs::create_dir_all(dir)?;
+        let mut clips = Vec::new();
+        for spec in &self.specs {
+            for step in 0..spec.pitches {
+                let variant = spec.variant(step);
+                let path = dir.join(format!("{}.wav", variant.name));
+                fs::File::create(&path)?.write_all(&variant.wav())?;
+                clips.push((variant.name, path));
+            }
+        }
+        Ok(clips)
+    }
+
+    // Clip name for an effect raised by `step` pitch steps, clamped to the variants rendered
+    pub fn pitched(&self, name: &str, step: usize) -> String {
+        let pitches = self
+            .specs
+            .iter()
+            .find(|spec| spec.name == name)
+            .map_or(1, |spec| spec.pitches);
+        variant_name(name, step.min(pitches.saturating_sub(1)))
+    }
+}
+
+fn parse_spec(line: &str) -> Result<SoundSpec, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 5 {
+        return Err(String::from("expected `name wave from_hz to_hz ms`"));
+    }
+    let wave = match fields[1] {
+        "square" => Waveform::Square,
+        "noise" => Waveform::Noise,
+        other => return Err(format!("unknown wave `{}`", other)),
+    };
+    let mut spec = SoundSpec {
+        name: fields[0].to_string(),
+        wave,
+        start_hz: parse_number(fields[2], "from_hz")?,
+        end_hz: parse_number(fields[3], "to_hz")?,
+        duration: Duration::from_millis(parse_number::<u64>(fields[4], "ms")?),
+        volume: 0.5,
+        duty: 0.5,
+        warble_hz: 0.0,
+        warble_depth: 0.0,
+        pitches: 1,
+    };
+
+    for option in &fields[5..] {
+        let (key, value) = option
+            .split_once('=')
+            .ok_or_else(|| format!("expected `key=value`, found `{}`", option))?;
+        match key {
+            "vol" => spec.volume = parse_number::<f32>(value, key)?.clamp(0.0, 1.0),
+            "duty" => spec.duty = parse_number::<f32>(value, key)?.clamp(0.0, 1.0),
+            "pitches" => spec.pitches = parse_number::<usize>(value, key)?.max(1),
+            "warble" => {
+                let (rate, depth) = value
+                    .split_once(':')
+                    .ok_or_else(|| String::from("expected `warble=rate_hz:depth`"))?;
+                spec.warble_hz = parse_number(rate, "warble rate")?;
+                spec.warble_depth = parse_number(depth, "warble depth")?;
+            }
+            _ => return Err(format!("unknown option `{}`", key)),
+        }
+    }
+    Ok(spec)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} `{}`", what, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_effect_with_options() {
+        // given
+        let source = "# comment\n\nexplode noise 900 80 350 vol=0.6 pitches=4\n";
+        // when
+        let pack = SoundPack::parse(source).unwrap();
+        // then
+        assert_eq!(1, pack.specs.len());
+        assert_eq!(Waveform::Noise, pack.specs[0].wave);
+        assert_eq!(Duration::from_millis(350), pack.specs[0].duration);
+        assert_eq!(4, pack.specs[0].pitches);
+    }
+
+    #[test]
+    fn should_report_line_of_invalid_effect() {
+        // given
+        let source = "pew square 1400 300 150\nboom sine 100 100 100\n";
+        // when
+        let actual = SoundPack::parse(source).err().unwrap();
+        // then
+        assert_eq!(2, actual.line);
+    }
+
+    #[test]
+    fn should_clamp_pitched_variant_to_rendered_range() {
+        // given
+        let pack = SoundPack::parse("explode noise 900 80 350 pitches=4").unwrap();
+        // when
+        let actual = pack.pitched("explode", 10);
+        // then
+        assert_eq!("explode+3", actual);
+        assert_eq!("pew", pack.pitched("pew", 2));
+    }
+
+    #[test]
+    fn should_write_wav_header_matching_samples() {
+        // given
+        let pack = SoundPack::parse("pew square 1400 300 100").unwrap();
+        // when
+        let wav = pack.specs[0].wav();
+        // then
+        assert_eq!(b"RIFF", &wav[0..4]);
+        assert_eq!(44 + 2 * (SAMPLE_RATE as usize / 10), wav.len());
+    }
+
+    #[test]
+    fn should_parse_builtin_pack() {
+        assert!(!SoundPack::builtin().specs.is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+// Turns a level into the invaders it sends. Each level has a threat budget: the
+// first levels follow the original Fibonacci waves, later ones grow steadily.
+// The budget buys soldiers, tougher elites and, later on, armoured invaders that
+// take several hits, and whatever doesn't fit on screen
+// at once arrives as reinforcements, one sub-wave after another.
+// Levels from a level pack bring their own formations instead, and every 10th
+// level is a boss fight with no wave of its own. From level 5 on, each level's army
+// moves in one of the movement patterns, in turn.
+use crate::{invaders::Kind, pack::LevelFile, pattern::Pattern};
+use std::sync::Arc;
+
+// Levels whose budget follows the Fibonacci series, as the original waves did
+const FIBONACCI_LEVELS: u16 = 10;
+// Extra threat per level after that
+const GROWTH: u32 = 4;
+// Threat, and points, of one elite
+pub const ELITE_COST: u32 = 3;
+// Elites make up at most this share of a wave, reached at ELITE_LEVELS
+const MAX_ELITE_SHARE: f32 = 0.6;
+const ELITE_LEVELS: u16 = 150;
+// Threat, and points, of one armoured invader
+pub const ARMOURED_COST: u32 = 5;
+// Armour takes a growing share of the budget after ARMOURED_FROM, up to its maximum at ARMOURED_LEVELS
+const MAX_ARMOURED_SHARE: f32 = 0.25;
+const ARMOURED_FROM: u16 = 25;
+const ARMOURED_LEVELS: u16 = 300;
+
+// One group of invaders on screen at the same time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubWave {
+    pub soldiers: usize,
+    pub elites: usize,
+    pub armoured: usize,
+}
+
+impl SubWave {
+    pub fn size(&self) -> usize {
+        self.soldiers + self.elites + self.armoured
+    }
+
+    // Its share of the level's threat budget
+    pub fn threat(&self) -> u32 {
+        (self.soldiers + self.elites * ELITE_COST as usize + self.armoured * ARMOURED_COST as usize)
+            as u32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wave {
+    pub level: u16,
+    pub budget: u32,
+    pub sub_waves: Vec<SubWave>, // The first is deployed at once, the rest as reinforcements
+    pub authored: Option<Arc<LevelFile>>, // Hand-made formations and settings, replacing the generated ones
+    pub boss: bool,                       // The mothership comes instead of a wave
+    pub pattern: Pattern,                 // How the army moves
+}
+
+impl Wave {
+    // The wave for `level` on a screen with room for `capacity` invaders at once
+    pub fn generate(level: u16, capacity: usize) -> Self {
+        let level = level.max(1);
+        let budget = budget(level);
+        let armour_share = (MAX_ARMOURED_SHARE * level.saturating_sub(ARMOURED_FROM) as f32
+            / (ARMOURED_LEVELS - ARMOURED_FROM) as f32)
+            .min(MAX_ARMOURED_SHARE);
+        let armoured = (budget as f32 * armour_share) as usize / ARMOURED_COST as usize;
+        let rest = budget as usize - armoured * ARMOURED_COST as usize;
+        let share =
+            (MAX_ELITE_SHARE * (level - 1) as f32 / (ELITE_LEVELS - 1) as f32).min(MAX_ELITE_SHARE);
+        let average = 1.0 + share * (ELITE_COST - 1) as f32;
+        let elites = (rest as f32 / average * share) as usize;
+        let soldiers = rest - elites * ELITE_COST as usize;
+
+        // Spread the kinds evenly over as few sub-waves as fit the screen
+        let total = soldiers + elites + armoured;
+        let count = total.div_ceil(capacity.max(1)).max(1);
+        let share_of =
+            |amount: usize, index: usize| amount / count + (index < amount % count) as usize;
+        let mut sub_waves: Vec<SubWave> = (0..count)
+            .map(|index| {
+                let elites = share_of(elites, index);
+                SubWave {
+                    soldiers: share_of(total, index) - elites,
+                    elites,
+                    armoured: 0,
+                }
+            })
+            .collect();
+        // Armour some of each sub-wave's soldiers, then wherever there are soldiers left
+        let mut left = armoured;
+        for (index, sub_wave) in sub_waves.iter_mut().enumerate() {
+            let armour = share_of(armoured, index).min(sub_wave.soldiers).min(left);
+            sub_wave.soldiers -= armour;
+            sub_wave.armoured += armour;
+            left -= armour;
+        }
+        for sub_wave in sub_waves.iter_mut() {
+            let armour = sub_wave.soldiers.min(left);
+            sub_wave.soldiers -= armour;
+            sub_wave.armoured += armour;
+            left -= armour;
+        }
+        Self {
+            level,
+            budget,
+            sub_waves,
+            authored: None,
+            boss: false,
+            pattern: Pattern::for_level(level),
+        }
+    }
+
+    // The wave a level file lays out
+    pub fn authored(level: u16, file: Arc<LevelFile>) -> Self {
+        let sub_waves: Vec<SubWave> = file
+            .formations
+            .iter()
+            .map(|spots| {
+                let count = |kind: Kind| spots.iter().filter(|spot| spot.kind == kind).count();
+                let (elites, armoured) = (count(Kind::Elite), count(Kind::Armoured));
+                SubWave {
+                    soldiers: spots.len() - elites - armoured,
+                    elites,
+                    armoured,
+                }
+            })
+            .collect();
+        let budget = sub_waves.iter().map(SubWave::threat).sum();
+        Self {
+            level,
+            budget,
+            sub_waves,
+            pattern: file.pattern.unwrap_or_default(),
+            authored: Some(file),
+            boss: false,
+        }
+    }
+
+    // A boss level: the mothership alone, sending its own minions
+    pub fn boss(level: u16) -> Self {
+        Self {
+            level,
+            budget: 0,
+            sub_waves: Vec::new(),
+            authored: None,
+            boss: true,
+            pattern: Pattern::Classic,
+        }
+    }
+
+    pub fn invaders(&self) -> usize {
+        self.sub_waves.iter().map(SubWave::size).sum()
+    }
+}
+
+// Threat budget of a level: 3, 5, 8, 13, ... up to level 10, then GROWTH more each level
+pub fn budget(level: u16) -> u32 {
+    let (mut a, mut b) = (3u32, 5u32);
+    for _ in 1..level.clamp(1, FIBONACCI_LEVELS) {
+        (a, b) = (b, a + b);
+    }
+    a + GROWTH * level.saturating_sub(FIBONACCI_LEVELS) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_follow_fibonacci_for_early_levels() {
+        // when
+        let budgets: Vec<u32> = (1..=11).map(budget).collect();
+        // then
+        assert_eq!(vec![3, 5, 8, 13, 21, 34, 55, 89, 144, 233, 237], budgets);
+    }
+
+    #[test]
+    fn should_spend_the_whole_budget_in_sub_waves_that_fit() {
+        for level in 1..=999 {
+            // when
+            let wave = Wave::generate(level, 48);
+            // then
+            let threat: u32 = wave.sub_waves.iter().map(SubWave::threat).sum();
+            assert_eq!(wave.budget, threat, "level {}", level);
+            assert!(wave.sub_waves.iter().all(|sub_wave| sub_wave.size() <= 48));
+        }
+    }
+
+    #[test]
+    fn should_make_every_level_distinct() {
+        // when
+        let waves: Vec<Wave> = (1..=999).map(|level| Wave::generate(level, 225)).collect();
+        // then
+        for pair in waves.windows(2) {
+            assert!(pair[0].budget < pair[1].budget);
+        }
+        assert_eq!(0, waves[0].sub_waves[0].elites);
+        assert_eq!(0, waves[ARMOURED_FROM as usize].sub_waves[0].armoured);
+        assert!(waves[998]
+            .sub_waves
+            .iter()
+            .all(|sub_wave| sub_wave.armoured > 0));
+        assert!(waves[998].sub_waves.len() > 1);
+    }
+}